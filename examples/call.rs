@@ -1,6 +1,5 @@
 use chrono::Utc;
 use std::env;
-use std::fs;
 
 /// Example on how to analyze a callsign.
 ///
@@ -14,10 +13,8 @@ pub fn main() {
         let file = &args[1];
         let call = &args[2];
 
-        // Read contents of the ClubLog XML file
-        let raw = fs::read_to_string(file).unwrap();
-        // Parse the contents into an object
-        let clublog = hamcall::clublog::ClubLog::parse(&raw).unwrap();
+        // Parse the ClubLog XML file into an object while streaming its contents
+        let clublog = hamcall::clublog::ClubLog::from_path(file).unwrap();
         // Convert the object for faster access times
         let clublogmap = hamcall::clublogmap::ClubLogMap::from(clublog);
 
@@ -26,17 +23,14 @@ pub fn main() {
 
         // Analyze the call to geht the entity, the ADIF identifier and a few more things
         match hamcall::call::analyze_callsign(&clublogmap, call, &timestamp) {
-            Ok(c) => {
-                // Check if the entity is whitelisted and if so, if the callsign is part of the whitelist
-                if hamcall::call::check_whitelist(&clublogmap, &c, &timestamp) {
-                    println!("{} => {:?}", call, c)
-                } else {
-                    println!(
-                        "Callsign matches to entity {} but is not whitelisted",
-                        c.dxcc.unwrap()
-                    )
-                }
-            }
+            // The result tells if the entity is whitelisted and if so, if the callsign is part of the whitelist
+            Ok(c) if c.whitelist.is_rejected() => println!(
+                "{} => Callsign matches to entity {} but is not whitelisted ({:?})",
+                call,
+                c.dxcc.unwrap_or_default(),
+                c.whitelist
+            ),
+            Ok(c) => println!("{} => {:?}", call, c),
             Err(e) => eprintln!("{} => {:?}", call, e),
         }
     }
@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use hamcall::call::{self, Callsign, CallsignError};
+use hamcall::clublog;
+use serde::Serialize;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+
+/// Single line of the output
+#[derive(Serialize)]
+struct Line<'a> {
+    /// Analyzed callsign
+    call: &'a str,
+    /// Timestamp of the analysis
+    timestamp: DateTime<Utc>,
+    /// Result of the analysis if the callsign is valid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    callsign: Option<&'a Callsign>,
+    /// Reason if the callsign is invalid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a CallsignError>,
+}
+
+/// Implementation to analyze multiple QSOs at once and emit the results as JSON lines.
+/// Reads the QSOs from a CSV file with the format `<CALL>,<QSO_DATE>,<TIME_ON>` where each column references an ADIF field with that name.
+/// Afterwards the callsigns are analyzed and the results are printed to stdout, one JSON object per line.
+///
+/// Usage: `json_lines <CLUBLOGXML> <CSVFILE>`
+pub fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() != 3 {
+        println!("Usage: `json_lines <CLUBLOGXML> <CSVFILE>`");
+    } else {
+        let xml = &args[1];
+        let fname = &args[2];
+
+        // Parse the ClubLog XML file into an object while streaming its contents
+        let clublog = clublog::ClubLog::from_path(xml).unwrap();
+        // Convert the object for faster access times
+        let clublogmap = hamcall::clublogmap::ClubLogMap::from(clublog);
+
+        // Read csv file
+        let csv = read_csv(fname);
+
+        // Analyze all callsigns at once, in parallel if the feature is enabled
+        let calls = csv.iter().map(|entry| (&entry.0, entry.1));
+        #[cfg(not(feature = "parallel"))]
+        let results = call::analyze_callsigns(&clublogmap, calls);
+        #[cfg(feature = "parallel")]
+        let results = call::analyze_callsigns_parallel(&clublogmap, calls);
+
+        // Print one line per entry
+        let mut stdout = io::stdout().lock();
+        for (entry, result) in csv.iter().zip(results.iter()) {
+            let line = Line {
+                call: &entry.0,
+                timestamp: entry.1,
+                callsign: result.as_ref().ok(),
+                error: result.as_ref().err(),
+            };
+            serde_json::to_writer(&mut stdout, &line).unwrap();
+            writeln!(stdout).unwrap();
+        }
+    }
+}
+
+/// Read csv file with the QSOs.
+///
+/// The csv file is assumed to have the following columns where the column names refer to ADIF fields
+/// `<CALL>,<QSO_DATE>,<TIME_ON>`
+fn read_csv(fname: &str) -> Vec<(String, DateTime<Utc>)> {
+    let mut result: Vec<(String, DateTime<Utc>)> = Vec::new();
+
+    let file = File::open(fname).unwrap();
+    let lines = BufReader::new(file).lines();
+
+    for line in lines {
+        let line = line.unwrap();
+
+        let splits: Vec<&str> = line.split(',').collect();
+
+        let call = String::from(splits[0]);
+        let timestamp: DateTime<Utc> = DateTime::parse_from_str(
+            &format!("{} {} +0000", splits[1], splits[2]),
+            "%Y%m%d %H%M %z",
+        )
+        .unwrap()
+        .into();
+
+        result.push((call, timestamp));
+    }
+
+    result
+}
@@ -2,7 +2,7 @@ use chrono::{DateTime, Utc};
 use hamcall::clublogquery::ClubLogQuery;
 use hamcall::{call, clublog};
 use std::env;
-use std::fs::{self, File};
+use std::fs::File;
 use std::io::{BufRead, BufReader};
 
 /// Implementation to test multiple QSOs at once.
@@ -20,20 +20,25 @@ pub fn main() {
         let xml = &args[1];
         let fname = &args[2];
 
-        // Read contents of the ClubLog XML file
-        let raw = fs::read_to_string(xml).unwrap();
-        // Parse the contents into an object
-        let clublog = clublog::ClubLog::parse(&raw).unwrap();
+        // Parse the ClubLog XML file into an object while streaming its contents
+        let clublog = clublog::ClubLog::from_path(xml).unwrap();
         // Convert the object for faster access times
         let clublogmap = hamcall::clublogmap::ClubLogMap::from(clublog);
 
         // Read csv file
         let csv = read_csv(fname);
 
+        // Analyze all callsigns at once, in parallel if the feature is enabled
+        let calls = csv.iter().map(|entry| (&entry.0, entry.2));
+        #[cfg(not(feature = "parallel"))]
+        let results = call::analyze_callsigns(&clublogmap, calls);
+        #[cfg(feature = "parallel")]
+        let results = call::analyze_callsigns_parallel(&clublogmap, calls);
+
         // Loop through each entry
-        for entry in csv {
-            // Analyze callsign and print comparison results
-            match call::analyze_callsign(&clublogmap, &entry.0, &entry.2) {
+        for (entry, result) in csv.iter().zip(results) {
+            // Print comparison results
+            match result {
                 Ok(c) => {
                     if entry.1 != c.adif {
                         let entity_theirs = &clublogmap
@@ -48,7 +53,7 @@ pub fn main() {
                         );
                         continue;
                     }
-                    if !call::check_whitelist(&clublogmap, &c, &entry.2) {
+                    if c.whitelist.is_rejected() {
                         eprintln!(
                             "{} => Callsign matches to entity {} but is not whitelisted",
                             &entry.0,
@@ -6,15 +6,24 @@
 //!
 //! The example `call.rs` shows the basic usage of this module.
 
-use crate::clublog::{Adif, CallsignException, CqZone, Prefix, ADIF_ID_NO_DXCC};
+use crate::callnormalize::{Fix, Normalization};
+use crate::callparts::{parse_callsign, CallsignParts};
+use crate::callrules::{Analysis, RuleChain};
+#[cfg(feature = "serde")]
+use crate::clublog::serialize_datetime_opt;
+use crate::clublog::{Adif, CallsignException, CqZone, Prefix, RecordId, ADIF_ID_NO_DXCC};
 use crate::clublogquery::ClubLogQuery;
 use chrono::{DateTime, Utc};
 use lazy_static::lazy_static;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use regex::Regex;
+use std::collections::HashMap;
 use thiserror::Error;
 
 /// Representation of a callsign together with detailed information like the name of the entity or the ADIF DXCC identifier.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Callsign {
     /// Complete callsign
     pub call: String,
@@ -30,6 +39,10 @@ pub struct Callsign {
     pub longitude: Option<f32>,
     /// Latitude
     pub latitude: Option<f32>,
+    /// Structure of the callsign
+    pub parts: CallsignParts,
+    /// Whitelist status of the callsign at the timestamp of the analysis
+    pub whitelist: WhitelistStatus,
 }
 
 impl Callsign {
@@ -51,11 +64,12 @@ impl Callsign {
     /// # Arguments
     ///
     /// - `call`: Callsign
+    /// - `parts`: Structure of the callsign
     ///
     /// # Returns
     ///
     /// Callsign struct
-    fn new_special_entity(call: &str) -> Callsign {
+    pub(crate) fn new_special_entity(call: &str, parts: CallsignParts) -> Callsign {
         Callsign {
             call: String::from(call),
             adif: ADIF_ID_NO_DXCC,
@@ -64,6 +78,8 @@ impl Callsign {
             continent: None,
             longitude: None,
             latitude: None,
+            parts,
+            whitelist: WhitelistStatus::NotApplicable,
         }
     }
 
@@ -73,11 +89,12 @@ impl Callsign {
     ///
     /// - `call`: Callsign
     /// - `prefix`: Callsign exception entry
+    /// - `parts`: Structure of the callsign
     ///
     /// # Returns
     ///
     /// Callsign struct
-    fn from_prefix(call: &str, prefix: &Prefix) -> Callsign {
+    pub(crate) fn from_prefix(call: &str, prefix: &Prefix, parts: CallsignParts) -> Callsign {
         Callsign {
             call: String::from(call),
             adif: prefix.adif,
@@ -86,6 +103,8 @@ impl Callsign {
             continent: prefix.cont.clone(),
             longitude: prefix.long,
             latitude: prefix.lat,
+            parts,
+            whitelist: WhitelistStatus::NotApplicable,
         }
     }
 
@@ -99,7 +118,7 @@ impl Callsign {
     /// # Returns
     ///
     /// Callsign struct
-    fn from_exception(call: &str, exc: &CallsignException) -> Callsign {
+    pub(crate) fn from_exception(call: &str, exc: &CallsignException) -> Callsign {
         Callsign {
             call: String::from(call),
             adif: exc.adif,
@@ -108,40 +127,280 @@ impl Callsign {
             continent: exc.cont.clone(),
             longitude: exc.long,
             latitude: exc.lat,
+            // The exception does not reveal the structure, therefore decompose the callsign by its format
+            parts: CallsignParts {
+                prefix: None,
+                ..parse_callsign(call).unwrap_or_default()
+            },
+            whitelist: WhitelistStatus::NotApplicable,
         }
     }
 }
 
+/// Whitelist status of a callsign, see [whitelist_status].
+///
+/// For some entities, ClubLog only accepts callsigns that are listed as callsign exception, for example for DXpeditions.
+/// The whitelist window is the time window in which the whitelisting of the entity is enabled, bounds of `None` are open.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "status", rename_all = "snake_case"))]
+pub enum WhitelistStatus {
+    /// Whitelisting is not enabled for the entity of the callsign or the callsign does not belong to an entity
+    #[default]
+    NotApplicable,
+    /// Callsign is on the whitelist of its entity
+    Whitelisted {
+        /// Identifier of the callsign exception record that lists the callsign
+        record: RecordId,
+        /// Begin of the whitelist window
+        #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_datetime_opt"))]
+        start: Option<DateTime<Utc>>,
+        /// End of the whitelist window
+        #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_datetime_opt"))]
+        end: Option<DateTime<Utc>>,
+    },
+    /// Whitelisting is enabled for the entity, but the timestamp is outside of the whitelist window
+    OutsideWindow {
+        /// Begin of the whitelist window
+        #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_datetime_opt"))]
+        start: Option<DateTime<Utc>>,
+        /// End of the whitelist window
+        #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_datetime_opt"))]
+        end: Option<DateTime<Utc>>,
+    },
+    /// Callsign is not on the whitelist of its entity
+    Rejected {
+        /// Identifier of the callsign exception record that lists the callsign for a different entity, if any
+        record: Option<RecordId>,
+        /// Begin of the whitelist window
+        #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_datetime_opt"))]
+        start: Option<DateTime<Utc>>,
+        /// End of the whitelist window
+        #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_datetime_opt"))]
+        end: Option<DateTime<Utc>>,
+    },
+}
+
+impl WhitelistStatus {
+    /// Check if the callsign was rejected by the whitelist of its entity
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// True if the callsign is not on the whitelist
+    pub fn is_rejected(&self) -> bool {
+        matches!(self, WhitelistStatus::Rejected { .. })
+    }
+}
+
 /// Possible reasons for an invalid callsign
-#[derive(Error, Debug, PartialEq)]
+#[derive(Error, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
 pub enum CallsignError {
     /// Callsign is of invalid format or includes invalid characters
-    #[error("Callsign is of invalid format or includes invalid characters")]
-    BasicFormat,
+    #[error("Callsign is of invalid format or includes invalid characters at position {position}")]
+    BasicFormat {
+        /// Position of the first offending character, counted in characters beginning at zero.
+        /// Equals the length of the callsign if a character is missing at its end.
+        position: usize,
+    },
 
     /// Callsign was used in an invalid operation
-    #[error("Callsign was used in an invalid operation")]
-    InvalidOperation,
+    #[error("Callsign was used in an invalid operation{}", fmt_invalid_operation(*.record, .start, .end))]
+    InvalidOperation {
+        /// Identifier of the invalid operation record, `None` if the record is unknown
+        record: Option<RecordId>,
+        /// Begin of the invalid operation, `None` if open
+        #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_datetime_opt"))]
+        start: Option<DateTime<Utc>>,
+        /// End of the invalid operation, `None` if open
+        #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_datetime_opt"))]
+        end: Option<DateTime<Utc>>,
+    },
 
     /// Callsign does not begin with a valid prefix
-    #[error("Callsign does not begin with a valid prefix")]
-    BeginWithoutPrefix,
+    #[error(
+        "Callsign does not begin with a valid prefix, part {part} at index {index} is no prefix"
+    )]
+    BeginWithoutPrefix {
+        /// Offending part of the callsign
+        part: String,
+        /// Index of the offending part within the parts of the callsign split by `/`
+        index: usize,
+    },
 
     /// Too much prefixes
-    #[error("Too much prefixes")]
-    TooMuchPrefixes,
+    #[error("Too much prefixes, part {part} at index {index} is one prefix too much")]
+    TooMuchPrefixes {
+        /// Offending part of the callsign
+        part: String,
+        /// Index of the offending part within the parts of the callsign split by `/`
+        index: usize,
+    },
 
     /// Multiple special appendices like /MM, /AM or /6, /8, ...
-    #[error("Multiple special appendices")]
-    MultipleSpecialAppendices,
+    #[error("Multiple special appendices {}", .appendices.join(", "))]
+    MultipleSpecialAppendices {
+        /// Conflicting appendices in the order of the callsign
+        appendices: Vec<String>,
+    },
+
+    /// Callsign is of valid format but was not whitelisted for its entity, see [verify_whitelist]
+    #[error("Callsign is not whitelisted for entity {entity} ({adif})")]
+    NotWhitelisted {
+        /// ADIF identifier of the entity
+        adif: Adif,
+        /// Name of the entity
+        entity: String,
+    },
+}
+
+/// Format the record and time window of an invalid operation for the error message.
+///
+/// # Arguments
+///
+/// - `record`: Identifier of the record, if known
+/// - `start`: Begin of the invalid operation
+/// - `end`: End of the invalid operation
+///
+/// # Returns
+///
+/// Formatted record and time window, empty if the record is unknown
+fn fmt_invalid_operation(
+    record: Option<RecordId>,
+    start: &Option<DateTime<Utc>>,
+    end: &Option<DateTime<Utc>>,
+) -> String {
+    let bound = |ts: &Option<DateTime<Utc>>| ts.map(|ts| ts.to_rfc3339()).unwrap_or_default();
+
+    match record {
+        Some(record) => format!(" (record {}, {} - {})", record, bound(start), bound(end)),
+        None => String::new(),
+    }
+}
+
+lazy_static! {
+    /// Rules of the callsign analysis
+    static ref DEFAULT_RULES: RuleChain = RuleChain::default();
+}
+
+/// Policy on how to interpret single letter appendices like the `A` of `SV1ABC/A` or the `F` of `LS4AA/F`
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum SingleLetterPolicy {
+    /// Try as special prefix like `SV/A` as well as standalone prefix like `F`
+    #[default]
+    Any,
+    /// Only try as special prefix like `SV/A`
+    SpecialPrefix,
+    /// Only try as standalone prefix like `F`
+    StandalonePrefix,
+    /// Ignore single letter appendices, e.g. since they are area designators
+    Ignore,
+}
+
+impl SingleLetterPolicy {
+    /// Check whether single letter appendices are tried as special prefix.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// True if special prefixes like `SV/A` are allowed
+    pub fn allows_special_prefix(&self) -> bool {
+        matches!(
+            self,
+            SingleLetterPolicy::Any | SingleLetterPolicy::SpecialPrefix
+        )
+    }
+
+    /// Check whether single letter appendices are tried as standalone prefix.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// True if standalone prefixes like `F` are allowed
+    pub fn allows_standalone_prefix(&self) -> bool {
+        matches!(
+            self,
+            SingleLetterPolicy::Any | SingleLetterPolicy::StandalonePrefix
+        )
+    }
+}
+
+/// Options of the callsign analysis, see [analyze_callsign_with]
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct AnalyzeOptions {
+    /// Normalization of the callsign before the analysis, the analyzed callsign is the normalized one
+    pub normalization: Normalization,
+    /// Policy for single letter appendices
+    pub single_letter: SingleLetterPolicy,
+    /// Policies for single letter appendices that replace the general one, by the ADIF identifier of the entity of the homecall
+    pub entity_single_letter: HashMap<Adif, SingleLetterPolicy>,
+}
+
+impl AnalyzeOptions {
+    /// Set the normalization of the callsign before the analysis.
+    ///
+    /// # Arguments
+    ///
+    /// - `normalization`: Mode of the normalization
+    ///
+    /// # Returns
+    ///
+    /// Modified options
+    pub fn with_normalization(mut self, normalization: Normalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Set the policy for single letter appendices.
+    ///
+    /// # Arguments
+    ///
+    /// - `policy`: Policy for single letter appendices
+    ///
+    /// # Returns
+    ///
+    /// Modified options
+    pub fn with_single_letter(mut self, policy: SingleLetterPolicy) -> Self {
+        self.single_letter = policy;
+        self
+    }
+
+    /// Set the policy for single letter appendices of callsigns with a homecall of a certain entity.
+    /// For example, Argentinian callsigns like `LS4AA/F` use single letters as area designators.
+    ///
+    /// # Arguments
+    ///
+    /// - `adif`: ADIF identifier of the entity of the homecall
+    /// - `policy`: Policy for single letter appendices
+    ///
+    /// # Returns
+    ///
+    /// Modified options
+    pub fn with_entity_single_letter(mut self, adif: Adif, policy: SingleLetterPolicy) -> Self {
+        self.entity_single_letter.insert(adif, policy);
+        self
+    }
 }
 
 /// Special appendices that may not be interpreted as prefixes
-const APPENDIX_SPECIAL: [&str; 7] = ["AM", "MM", "SAT", "P", "M", "QRP", "LH"];
+pub(crate) const APPENDIX_SPECIAL: [&str; 7] = ["AM", "MM", "SAT", "P", "M", "QRP", "LH"];
 
 /// Type of split
-#[derive(PartialEq, Eq)]
-enum PartType {
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum PartType {
     /// Prefix
     Prefix,
     /// Everything other than a prefix
@@ -149,18 +408,121 @@ enum PartType {
 }
 
 /// State of the call element classification statemachine
-#[derive(PartialEq, Eq)]
-enum State {
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum State {
     /// No prefix found so far
     NoPrefix,
     /// Single prefix
     SinglePrefix,
     /// Double prefix
     DoublePrefix,
-    /// Found complete prefix, only appendices may follow
+    /// Found complete prefix with the given number of prefixes, only appendices may follow
     PrefixComplete(u8),
 }
 
+/// Prefix found by [get_prefix] together with the number of removed chars
+pub(crate) type FoundPrefix<'a> = (&'a Prefix, usize);
+
+/// Prefix matched against a part of a callsign
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrefixMatch {
+    /// Identifier of the prefix record
+    pub record: RecordId,
+    /// Prefix as listed in the ClubLog data, e.g. `SV/A`
+    pub call: String,
+    /// Number of chars removed from the end of the part before it matched the prefix
+    pub stripped: usize,
+}
+
+impl PrefixMatch {
+    /// Instantiate a new prefix match from the result of [get_prefix].
+    ///
+    /// # Arguments
+    ///
+    /// - `prefix`: Matched prefix together with the number of removed chars
+    ///
+    /// # Returns
+    ///
+    /// Prefix match
+    pub(crate) fn new(prefix: FoundPrefix) -> PrefixMatch {
+        PrefixMatch {
+            record: prefix.0.record,
+            call: prefix.0.call.clone(),
+            stripped: prefix.1,
+        }
+    }
+}
+
+/// Single part of a callsign split by `/`
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Part {
+    /// Part of the callsign
+    pub part: String,
+    /// Classification of the part
+    pub parttype: PartType,
+    /// Prefix matched against the part, even if the part was not classified as prefix
+    pub prefix: Option<PrefixMatch>,
+}
+
+/// Trace of the decisions made while analyzing a callsign, see [analyze_callsign_explained].
+///
+/// The trace ends at the step where the analysis finished, for example if a callsign exception applies, neither parts nor states are present.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Explanation {
+    /// Identifier of the callsign exception record that applied
+    pub exception: Option<RecordId>,
+    /// Parts of the callsign together with their classification
+    pub parts: Vec<Part>,
+    /// Path through the classification statemachine, beginning with the initial state followed by the state after each part
+    pub states: Vec<State>,
+    /// Prefix the result is based on
+    pub prefix: Option<PrefixMatch>,
+    /// Special appendix like `MM` that indicates that the callsign does not belong to an entity
+    pub special_appendix: Option<String>,
+    /// Single digit appendix that changed the prefix, e.g. `9` for `SV0ABC/9`
+    pub single_digit_appendix: Option<String>,
+    /// CQ zone of the zone exception that applied
+    pub zone_exception: Option<CqZone>,
+    /// Fixes applied while normalizing the callsign
+    pub fixes: Vec<Fix>,
+}
+
+/// Rule of the analysis that lead to an interpretation of a callsign
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Rule {
+    /// Callsign exception
+    CallsignException,
+    /// Prefix of the homecall, e.g. `W` for `W1ABC/P`
+    HomecallPrefix,
+    /// Special appendix like `/MM` that indicates that the callsign does not belong to an entity
+    SpecialAppendix,
+    /// Single digit appendix that changes the prefix of the homecall, e.g. `SV0ABC/9`
+    SingleDigitAppendix,
+    /// Special prefix made of a prefix and an appendix, e.g. `SV/A` for `SV1ABC/A`
+    SpecialPrefix,
+    /// Prefix of the part with the given index out of two parts that are both valid prefixes, e.g. `0` for `CE0Y/PG5M`
+    DoublePrefix(usize),
+}
+
+/// Possible interpretation of a callsign, see [analyze_callsign_alternatives]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Interpretation {
+    /// Information about the callsign
+    pub callsign: Callsign,
+    /// Confidence of the interpretation between zero and one, the confidences of all interpretations of a callsign sum up to one
+    pub confidence: f32,
+    /// Rule that lead to the interpretation
+    pub rule: Rule,
+}
+
 /// Check if the callsign is whitelisted if the whitelist option is enabled for the entity of the callsign at the given point in time.
 ///
 /// # Arguments
@@ -177,39 +539,97 @@ pub fn check_whitelist(
     call: &Callsign,
     timestamp: &DateTime<Utc>,
 ) -> bool {
+    !whitelist_status(clublog, call, timestamp).is_rejected()
+}
+
+/// Verify that the callsign is whitelisted if the whitelist option is enabled for the entity of the callsign at the given point in time.
+///
+/// Same as [check_whitelist] but reports the entity the callsign is not whitelisted for.
+///
+/// # Arguments
+///
+/// - `clublog`: Reference to ClubLog data
+/// - `call`: Callsign to check
+/// - `timestamp`: Timestamp to use for the check
+///
+/// # Returns
+///
+/// Returns `Ok` if the callsign is valid or [CallsignError::NotWhitelisted] if whitelisting for that entity is enabled and the callsign is not on the whitelist.
+pub fn verify_whitelist(
+    clublog: &dyn ClubLogQuery,
+    call: &Callsign,
+    timestamp: &DateTime<Utc>,
+) -> Result<(), CallsignError> {
+    match whitelist_status(clublog, call, timestamp) {
+        WhitelistStatus::Rejected { .. } => Err(CallsignError::NotWhitelisted {
+            adif: call.adif,
+            entity: clublog
+                .get_entity(call.adif, timestamp)
+                .map(|e| e.name.clone())
+                .unwrap_or_default(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Determine the whitelist status of the callsign at the given point in time.
+///
+/// The result of the [callsign analysis](analyze_callsign) already contains the status at the timestamp of the analysis.
+///
+/// # Arguments
+///
+/// - `clublog`: Reference to ClubLog data
+/// - `call`: Callsign to check
+/// - `timestamp`: Timestamp to use for the check
+///
+/// # Returns
+///
+/// Whitelist status of the callsign
+pub fn whitelist_status(
+    clublog: &dyn ClubLogQuery,
+    call: &Callsign,
+    timestamp: &DateTime<Utc>,
+) -> WhitelistStatus {
     // Get entity for adif identifier
     // Note that not all valid adif identifiers refer to an entity (e.g. aeronautical mobile calls)
-    if let Some(entity) = clublog.get_entity(call.adif, timestamp) {
+    let entity = match clublog.get_entity(call.adif, timestamp) {
         // Check if whitelisting is enabled
-        if entity.whitelist == Some(true) {
-            // Check if an exception for the call at the given point in time is present
-            if let Some(prefix) = clublog.get_callsign_exception(&call.call, timestamp) {
-                // There may be a callsign exception for a whitelisted entity but the exception refers a different adif identifier
-                return prefix.adif == call.adif;
-            }
-
-            // Check if the given point in time is before the start of whitelisting for that entity
-            if let Some(whitelist_start) = entity.whitelist_start {
-                if *timestamp < whitelist_start {
-                    return true;
-                }
-            }
-
-            // Check if the given point in time is after the end of whitelisting for that entity
-            if let Some(whitelist_end) = entity.whitelist_end {
-                if *timestamp > whitelist_end {
-                    return true;
-                }
-            }
+        Some(entity) if entity.whitelist == Some(true) => entity,
+        _ => return WhitelistStatus::NotApplicable,
+    };
+    let (start, end) = (entity.whitelist_start, entity.whitelist_end);
+
+    // Check if an exception for the call at the given point in time is present
+    if let Some(exc) = clublog.get_callsign_exception(&call.call, timestamp) {
+        // There may be a callsign exception for a whitelisted entity but the exception refers a different adif identifier
+        return match exc.adif == call.adif {
+            true => WhitelistStatus::Whitelisted {
+                record: exc.record,
+                start,
+                end,
+            },
+            false => WhitelistStatus::Rejected {
+                record: Some(exc.record),
+                start,
+                end,
+            },
+        };
+    }
 
-            return false;
-        }
+    // Check if the given point in time is before the start or after the end of whitelisting for that entity
+    if start.is_some_and(|start| *timestamp < start) || end.is_some_and(|end| *timestamp > end) {
+        return WhitelistStatus::OutsideWindow { start, end };
     }
 
-    true
+    WhitelistStatus::Rejected {
+        record: None,
+        start,
+        end,
+    }
 }
 
 /// Analyze callsign to get further information like the name of the entity or the AIDF DXCC identifier.
+/// The analysis applies the [default rule chain](crate::callrules::RuleChain::default), use a custom [RuleChain] to modify the analysis.
 ///
 /// # Arguments:
 ///
@@ -225,174 +645,269 @@ pub fn analyze_callsign(
     call: &str,
     timestamp: &DateTime<Utc>,
 ) -> Result<Callsign, CallsignError> {
-    // Strategy
-    // Step 1: Check for an invalid operation
-    // Step 2: Check for a callsign exception
-    // Step 3: Classify each part of the callsign (split by '/') if it is a valid prefix or not
-    // Step 4: Check for basic validity of the callsign by using the classification results and categorize the call into generic callsign structures
-    // Step 5: Handle the call based on the determined category
+    DEFAULT_RULES.analyze(clublog, call, timestamp, &AnalyzeOptions::default())
+}
 
-    lazy_static! {
-        static ref RE_COMPLETE_CALL: Regex = Regex::new(r"^[A-Z0-9]+[A-Z0-9/]*[A-Z0-9]+$").unwrap();
-    }
+/// Analyze callsign like [analyze_callsign] does, but with custom options.
+///
+/// # Arguments:
+///
+/// - `clublog`: Reference to ClubLog data
+/// - `call`: Callsign to analyze
+/// - `timestamp`: Timestamp to use for the check
+/// - `options`: Options of the analysis
+///
+/// # Returns
+///
+/// Returns further information about the callsign or an error.
+pub fn analyze_callsign_with(
+    clublog: &dyn ClubLogQuery,
+    call: &str,
+    timestamp: &DateTime<Utc>,
+    options: &AnalyzeOptions,
+) -> Result<Callsign, CallsignError> {
+    DEFAULT_RULES.analyze(clublog, call, timestamp, options)
+}
 
-    // Check that only allowed characters are present and the callsign does not begin or end with a /
-    if !RE_COMPLETE_CALL.is_match(call) {
-        return Err(CallsignError::BasicFormat);
-    }
+/// Analyze callsign like [analyze_callsign] does and trace the decisions made on the way.
+/// This helps to understand why a callsign results in a certain entity or error.
+///
+/// # Arguments:
+///
+/// - `clublog`: Reference to ClubLog data
+/// - `call`: Callsign to analyze
+/// - `timestamp`: Timestamp to use for the check
+///
+/// # Returns
+///
+/// Returns further information about the callsign or an error together with the trace of the analysis.
+pub fn analyze_callsign_explained(
+    clublog: &dyn ClubLogQuery,
+    call: &str,
+    timestamp: &DateTime<Utc>,
+) -> (Result<Callsign, CallsignError>, Explanation) {
+    analyze_callsign_explained_with(clublog, call, timestamp, &AnalyzeOptions::default())
+}
 
-    // ### Step 1 ###
-    // Check if the callsign was used in an invalid operation
-    if clublog.is_invalid_operation(call, timestamp) {
-        return Err(CallsignError::InvalidOperation);
-    }
+/// Analyze callsign like [analyze_callsign_explained] does, but with custom options.
+///
+/// # Arguments:
+///
+/// - `clublog`: Reference to ClubLog data
+/// - `call`: Callsign to analyze
+/// - `timestamp`: Timestamp to use for the check
+/// - `options`: Options of the analysis
+///
+/// # Returns
+///
+/// Returns further information about the callsign or an error together with the trace of the analysis.
+pub fn analyze_callsign_explained_with(
+    clublog: &dyn ClubLogQuery,
+    call: &str,
+    timestamp: &DateTime<Utc>,
+    options: &AnalyzeOptions,
+) -> (Result<Callsign, CallsignError>, Explanation) {
+    DEFAULT_RULES.analyze_explained(clublog, call, timestamp, options)
+}
 
-    // ### Step 2 ###
-    // Check if clublog lists a callsign exception
-    if let Some(call_exc) = clublog.get_callsign_exception(call, timestamp) {
-        return Ok(Callsign::from_exception(call, call_exc));
-    }
+/// Analyze callsign and return all plausible interpretations, ranked by their confidence.
+/// On the same confidence, the interpretation returned by [analyze_callsign] comes first.
+///
+/// Alternatives exist for callsigns with two valid prefixes like `CE0Y/PG5M` and callsigns matching a special prefix like `SV1ABC/A`, where the regular prefix `SV` is plausible as well.
+/// The confidence of each interpretation is based on the number of chars removed from the part of the callsign before it matched the prefix, the less chars were removed the higher the confidence.
+/// Interpretations resulting in the same entity are merged into the one returned by [analyze_callsign].
+/// Therefore, more than one interpretation indicates that the entity of the callsign is ambiguous.
+///
+/// # Arguments:
+///
+/// - `clublog`: Reference to ClubLog data
+/// - `call`: Callsign to analyze
+/// - `timestamp`: Timestamp to use for the check
+///
+/// # Returns
+///
+/// Returns the interpretations of the callsign, ordered by their confidence, or an error.
+pub fn analyze_callsign_alternatives(
+    clublog: &dyn ClubLogQuery,
+    call: &str,
+    timestamp: &DateTime<Utc>,
+) -> Result<Vec<Interpretation>, CallsignError> {
+    analyze_callsign_alternatives_with(clublog, call, timestamp, &AnalyzeOptions::default())
+}
 
-    // Split raw callsign into its parts
+/// Analyze callsign like [analyze_callsign_alternatives] does, but with custom options.
+///
+/// # Arguments:
+///
+/// - `clublog`: Reference to ClubLog data
+/// - `call`: Callsign to analyze
+/// - `timestamp`: Timestamp to use for the check
+/// - `options`: Options of the analysis
+///
+/// # Returns
+///
+/// Returns the interpretations of the callsign, ordered by their confidence, or an error.
+pub fn analyze_callsign_alternatives_with(
+    clublog: &dyn ClubLogQuery,
+    call: &str,
+    timestamp: &DateTime<Utc>,
+    options: &AnalyzeOptions,
+) -> Result<Vec<Interpretation>, CallsignError> {
+    let (result, explanation) = DEFAULT_RULES.analyze_explained(clublog, call, timestamp, options);
+    let callsign = result?;
+    // The analyzed callsign may differ from the given one due to the normalization
+    let call = callsign.call.clone();
     let parts: Vec<&str> = call.split('/').collect();
 
-    // ### Step 3 ###
-    // Iterate through all parts of the callsign and check wether the part of the callsigns is a valid prefix or something else
-    let mut parttypes: Vec<PartType> = Vec::with_capacity(parts.len());
-    for (pos, part) in parts.iter().enumerate() {
-        let pt = if get_prefix(clublog, part, timestamp, &parts[pos + 1..]).is_some() {
-            // MM and AM may be valid prefixes or special appendices depending on the position within the complete callsign.
-            // For example MM as a prefix evaluates to Scotland, MM as an appendix indicates a maritime mobile activation.
-            // Special appendices are only valid as those if they are right at the beginning of the callsign.
-            // Therefore ignore the first element of the call and check for special appendices beginning from the second element onwards.
-            if pos >= 1 && APPENDIX_SPECIAL.contains(part) {
-                PartType::Other
-            } else {
-                PartType::Prefix
-            }
-        } else {
-            PartType::Other
-        };
-        parttypes.push(pt);
-    }
-
-    // ### Step 4 ###
-    // Check for basic validity with a small statemachine.
-    // For example check that the call begins with a prefix, has not too much prefixes, ...
-    let mut state = State::NoPrefix;
-    for parttype in parttypes.iter() {
-        match (&state, parttype) {
-            (State::NoPrefix, PartType::Prefix) => state = State::SinglePrefix,
-            (State::NoPrefix, PartType::Other) => Err(CallsignError::BeginWithoutPrefix)?,
-            (State::SinglePrefix, PartType::Prefix) => state = State::DoublePrefix,
-            (State::SinglePrefix, PartType::Other) => state = State::PrefixComplete(1),
-            (State::DoublePrefix, PartType::Prefix) => state = State::PrefixComplete(3),
-            (State::DoublePrefix, PartType::Other) => state = State::PrefixComplete(2),
-            (State::PrefixComplete(_), PartType::Prefix) => Err(CallsignError::TooMuchPrefixes)?,
-            (State::PrefixComplete(_), PartType::Other) => (),
-        }
-    }
-
-    // ### Step 5 ###
-    match state {
-        // The callsign consists of a single prefix and zero or more appendices
-        State::SinglePrefix | State::PrefixComplete(1) => {
-            // Complete homecall
-            // Example: W1AW
-            let homecall = &parts[0];
-
-            // Prefix of the homecall
-            // Example: W for the homecall W1AW
-            // Unwrap is safe here, otherwise there is an internal error
-            let mut homecall_prefix = get_prefix(clublog, homecall, timestamp, &parts[1..])
-                .unwrap()
-                .0;
-
-            // Special appendix like /AM or /MM is present
-            // Example: W1ABC/AM
-            if is_no_entity_by_appendix(&parts[1..])? {
-                return Ok(Callsign::new_special_entity(call));
-            }
-
-            // Check if a single digit appendix is present
-            // If so, check if the single digit appendix changes the prefix to a different one
-            // Example: "SV0ABC/9" where SV is Greece, but SV9 is Crete
-            if let Some(pref) = is_different_prefix_by_single_digit_appendix(
-                clublog,
-                homecall,
-                timestamp,
-                &parts[1..],
-            )? {
-                homecall_prefix = pref;
-            }
+    // Determine the rule that lead to the result
+    let double_prefix = matches!(
+        explanation.states.last(),
+        Some(State::DoublePrefix | State::PrefixComplete(2))
+    );
+    let special_prefix = explanation
+        .prefix
+        .as_ref()
+        .is_some_and(|p| p.call.contains('/'));
+    let rule = if explanation.exception.is_some() {
+        Rule::CallsignException
+    } else if explanation.special_appendix.is_some() {
+        Rule::SpecialAppendix
+    } else if explanation.single_digit_appendix.is_some() {
+        Rule::SingleDigitAppendix
+    } else if special_prefix {
+        Rule::SpecialPrefix
+    } else if double_prefix {
+        Rule::DoublePrefix(match explanation.parts[0].prefix == explanation.prefix {
+            true => 0,
+            false => 1,
+        })
+    } else {
+        Rule::HomecallPrefix
+    };
 
-            // No special rule matched, just return information
-            let mut callsign = Callsign::from_prefix(call, homecall_prefix);
-            check_apply_cqzone_exception(clublog, &mut callsign, timestamp);
-            Ok(callsign)
-        }
-        // The callsign consists of two prefixes and zero or more appendices
-        State::DoublePrefix | State::PrefixComplete(2) => {
-            // Get prefix information for both prefixes.
-            let pref_first = get_prefix(clublog, parts[0], timestamp, &parts[1..]).unwrap();
-            let pref_second = get_prefix(clublog, parts[1], timestamp, &parts[2..]).unwrap();
-
-            // Check if the first prefix may be a valid special prefix like 3D2/R
-            // Example: "3D2ABC/R" contains two valid prefixes at first sight, 3D2 and R but the first and second prefix together form the special prefix 3D2/R
-            let pref = if pref_first.0.call.contains('/') {
-                pref_first.0
-            } else {
-                // Decide which one to use by how many characters were removed from the potential prefix before it matched a prefix from the list.
-                // The prefix which required less character removals wins.
-                // This is probably not 100% correct, but seems good enough.
-                if pref_first.1 <= pref_second.1 {
-                    pref_first.0
-                } else {
-                    pref_second.0
-                }
+    // Search for an alternative prefix together with the index of the homecall and the location prefix
+    let alternative = match rule {
+        // Example: "CE0Y/PG5M" where the second part is a valid prefix as well
+        // The prefix is searched like within the analysis, so the policy for single letter appendices applies
+        Rule::DoublePrefix(idx) => Analysis::new(clublog, &call, timestamp, options)
+            .find_prefix(1 - idx)
+            .map(|p| (p, idx, Some(1 - idx), Rule::DoublePrefix(1 - idx))),
+        // Example: "SV1ABC/A" where SV is plausible as well, or "3D2/W1ABC/R" where 3D2 is plausible as location prefix
+        Rule::SpecialPrefix => {
+            // Part of the callsign that matched the special prefix
+            let idx = explanation
+                .parts
+                .iter()
+                .position(|p| p.prefix.is_some() && p.prefix == explanation.prefix)
+                .unwrap_or(0);
+            let (homecall, location, rule) = match double_prefix {
+                true => (1 - idx, Some(idx), Rule::DoublePrefix(idx)),
+                false => (idx, None, Rule::HomecallPrefix),
             };
-
-            let mut callsign = Callsign::from_prefix(call, pref);
-            check_apply_cqzone_exception(clublog, &mut callsign, timestamp);
-            Ok(callsign)
+            clublog
+                .longest_prefix_match(parts[idx], timestamp)
+                .map(|p| {
+                    (
+                        (p, parts[idx].len() - p.call.len()),
+                        homecall,
+                        location,
+                        rule,
+                    )
+                })
         }
-        // The callsign consists out of three prefixes and zero or more appendices
-        // This is a very special case and only takes account of calls with a special prefix like 3D2/R and therefore callsigns like 3D2/W1ABC/R.
-        // Calls like 3D2ABC/R are already covered, since there are only two potential valid prefixes.
-        // The call 3D2/W1ABC/R contains three potential valid prefixes 3D2, W and R but 3D2/R is the actual prefix (according to my understanding of the special prefix annotation)
-        State::PrefixComplete(3) => {
-            let pref = get_prefix(clublog, parts[0], timestamp, &parts[1..]).unwrap();
-            if pref.0.call.contains('/') {
-                let mut callsign = Callsign::from_prefix(call, pref.0);
-                check_apply_cqzone_exception(clublog, &mut callsign, timestamp);
-                Ok(callsign)
-            } else {
-                Err(CallsignError::TooMuchPrefixes)
+        _ => None,
+    };
+
+    // Weight the interpretations by the number of removed chars
+    let weight = |stripped: usize| 1.0 / (1.0 + stripped as f32);
+    let mut interpretations = vec![Interpretation {
+        callsign,
+        confidence: explanation
+            .prefix
+            .as_ref()
+            .map_or(1.0, |p| weight(p.stripped)),
+        rule,
+    }];
+
+    if let Some((pref, homecall, location, rule)) = alternative {
+        if pref.0.adif == interpretations[0].callsign.adif {
+            interpretations[0].confidence += weight(pref.1);
+        } else {
+            let structure =
+                CallsignParts::from_parts(&parts, homecall, location, Some(&pref.0.call));
+            let mut callsign = Callsign::from_prefix(&call, pref.0, structure);
+            if let Some(cqz) = explanation.zone_exception {
+                callsign.cqzone = Some(cqz);
             }
+            callsign.whitelist = whitelist_status(clublog, &callsign, timestamp);
+            interpretations.push(Interpretation {
+                callsign,
+                confidence: weight(pref.1),
+                rule,
+            });
         }
-        _ => panic!("Internal error"),
     }
+
+    // Normalize the confidences and rank the interpretations, the sort keeps the result of the analysis first on the same confidence
+    let total: f32 = interpretations.iter().map(|i| i.confidence).sum();
+    for interpretation in interpretations.iter_mut() {
+        interpretation.confidence /= total;
+    }
+    interpretations.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+
+    Ok(interpretations)
 }
 
-/// Check if a CQ zone exception exists based on the gathered callsign information.
-/// If there is one, replace the CQ zone directly in the given callsign struct.
+/// Analyze multiple callsigns, like all QSOs of a log, one after another.
 ///
 /// # Arguments
 ///
 /// - `clublog`: Reference to ClubLog data
-/// - `call`: Gathered callsign information
-/// - `timestamp`: Timestamp to use for the check
+/// - `calls`: Callsigns to analyze together with the timestamp to use for the check
 ///
 /// # Returns
-/// (None)
-fn check_apply_cqzone_exception(
+///
+/// Result of [analyze_callsign] for each callsign, in the same order as the input
+pub fn analyze_callsigns<I, S>(
     clublog: &dyn ClubLogQuery,
-    call: &mut Callsign,
-    timestamp: &DateTime<Utc>,
-) {
-    if let Some(cqz) = clublog.get_zone_exception(&call.call, timestamp) {
-        call.cqzone = Some(cqz);
-    }
+    calls: I,
+) -> Vec<Result<Callsign, CallsignError>>
+where
+    I: IntoIterator<Item = (S, DateTime<Utc>)>,
+    S: AsRef<str>,
+{
+    calls
+        .into_iter()
+        .map(|(call, timestamp)| analyze_callsign(clublog, call.as_ref(), &timestamp))
+        .collect()
+}
+
+/// Analyze multiple callsigns, like all QSOs of a log, in parallel on all available cores.
+/// Since the ClubLog data is shared between the threads, it must be [Sync].
+///
+/// # Arguments
+///
+/// - `clublog`: Reference to ClubLog data
+/// - `calls`: Callsigns to analyze together with the timestamp to use for the check
+///
+/// # Returns
+///
+/// Result of [analyze_callsign] for each callsign, in the same order as the input
+#[cfg(feature = "parallel")]
+pub fn analyze_callsigns_parallel<I, S>(
+    clublog: &(dyn ClubLogQuery + Sync),
+    calls: I,
+) -> Vec<Result<Callsign, CallsignError>>
+where
+    I: IntoIterator<Item = (S, DateTime<Utc>)>,
+    S: AsRef<str> + Sync,
+{
+    let calls: Vec<(S, DateTime<Utc>)> = calls.into_iter().collect();
+
+    calls
+        .par_iter()
+        .map(|(call, timestamp)| analyze_callsign(clublog, call.as_ref(), timestamp))
+        .collect()
 }
 
 /// Check if the list of appendices contains an appendix with a single digit that may indicate a different prefix.
@@ -409,19 +924,19 @@ fn check_apply_cqzone_exception(
 ///
 /// # Returns
 ///
-/// A potential new prefix, `None` if nothing changed or an error.
-fn is_different_prefix_by_single_digit_appendix<'a>(
+/// The single digit appendix together with the potential new prefix and the number of removed chars, `None` if nothing changed or an error.
+pub(crate) fn is_different_prefix_by_single_digit_appendix<'a, 'b>(
     clublog: &'a dyn ClubLogQuery,
     homecall: &str,
     timestamp: &DateTime<Utc>,
-    appendices: &[&str],
-) -> Result<Option<&'a Prefix>, CallsignError> {
+    appendices: &[&'b str],
+) -> Result<Option<(&'b str, FoundPrefix<'a>)>, CallsignError> {
     lazy_static! {
         static ref RE: Regex = Regex::new(r"^([A-Z0-9]+)(\d)([A-Z0-9]+)$").unwrap();
     }
 
     // Search for single digits in the list of appendices
-    let single_digits: Vec<&&str> = appendices
+    let single_digits: Vec<&&'b str> = appendices
         .iter()
         .filter(|e| {
             if e.len() == 1 {
@@ -439,13 +954,17 @@ fn is_different_prefix_by_single_digit_appendix<'a>(
         // If there is only a single digit, take it
         1 => single_digits[0],
         // For multiple single digits throw an error -> not sure which one to choose? Ignoring all would also be unexpected behaviour
-        _ => return Err(CallsignError::MultipleSpecialAppendices),
+        _ => {
+            return Err(CallsignError::MultipleSpecialAppendices {
+                appendices: single_digits.iter().map(|e| String::from(**e)).collect(),
+            })
+        }
     };
 
     // Assemble potential new intermediate call that will be used to check for a potential different prefix
     let new_homecall = RE.replace(homecall, format!("${{1}}{}${{3}}", new_digit));
 
-    Ok(get_prefix(clublog, &new_homecall, timestamp, appendices).map(|i| i.0))
+    Ok(get_prefix(clublog, &new_homecall, timestamp, appendices).map(|i| (*new_digit, i)))
 }
 
 /// Check if a special appendix (`MM`, `AM`, `SAT`) is part of the appendices list.
@@ -459,27 +978,31 @@ fn is_different_prefix_by_single_digit_appendix<'a>(
 ///
 /// # Returns
 ///
-/// True if a special prefix is present, false if not. Otherwise an error is returned.
-fn is_no_entity_by_appendix(appendices: &[&str]) -> Result<bool, CallsignError> {
-    let special_cnt = appendices
+/// The special appendix if present, `None` if not. Otherwise an error is returned.
+pub(crate) fn get_no_entity_appendix<'a>(
+    appendices: &[&'a str],
+) -> Result<Option<&'a str>, CallsignError> {
+    let specials: Vec<&&str> = appendices
         .iter()
         .filter(|e| **e == "MM" || **e == "AM" || **e == "SAT")
-        .count();
+        .collect();
 
     // Act based on how much special appendices were found
-    match special_cnt {
+    match specials.len() {
         // Zero found, nothing to do
-        0 => Ok(false),
+        0 => Ok(None),
         // Single one found, return it
-        1 => Ok(true),
+        1 => Ok(Some(specials[0])),
         // Multiple found, throw an error -> which one to choose?
-        _ => Err(CallsignError::MultipleSpecialAppendices),
+        _ => Err(CallsignError::MultipleSpecialAppendices {
+            appendices: specials.iter().map(|e| String::from(**e)).collect(),
+        }),
     }
 }
 
-/// Search for a matching prefix by brutforcing all possibilities.
-/// The potential prefix will be shortened char by char from the back until a prefix matches.
-/// Furthermore, to take in account of prefixes like SV/A, append all single char appendices to the end of the potential prefix before checking for a match.
+/// Search for the longest matching prefix.
+/// To take in account of prefixes like SV/A, special prefixes made of the potential prefix and a single char appendix are searched as well.
+/// On the same length, a special prefix takes precedence over a regular one.
 ///
 /// # Arguments
 ///
@@ -491,54 +1014,45 @@ fn is_no_entity_by_appendix(appendices: &[&str]) -> Result<bool, CallsignError>
 /// # Returns
 ///
 /// If there is a match, next to the prefix information the number of removed chars is returned.
-fn get_prefix<'a>(
+pub(crate) fn get_prefix<'a>(
     clublog: &'a dyn ClubLogQuery,
     potential_prefix: &str,
     timestamp: &DateTime<Utc>,
     appendices: &[&str],
-) -> Option<(&'a Prefix, usize)> {
-    let len_potential_prefix = potential_prefix.len();
-    assert!(len_potential_prefix >= 1);
+) -> Option<FoundPrefix<'a>> {
+    assert!(!potential_prefix.is_empty());
 
-    // Search for single char appendices
-    // For example SV/A is a valid prefix but indicates a different entity as the prefix SV
-    let single_char_appendices: Vec<&&str> = appendices
+    // Search for special prefixes with single char appendices
+    // For example SV/A is a valid prefix but indicates a different entity as the prefix SV, therefore the callsign SV1ABC/A shall match SV/A
+    // The length of the match is the length of the prefix without the appendix, the first appendix wins on the same length
+    let special = appendices
         .iter()
-        .filter(|e| {
-            if e.len() == 1 {
-                e.chars().next().unwrap().is_alphabetic()
-            } else {
-                false
-            }
+        .filter(|a| a.len() == 1 && a.chars().next().unwrap().is_alphabetic())
+        .filter_map(|a| {
+            clublog
+                .longest_prefix_match_with_appendix(potential_prefix, a, timestamp)
+                .map(|p| (p, p.call.len() - a.len() - 1))
         })
-        .collect();
+        .fold(None, |best: Option<(&Prefix, usize)>, cur| match best {
+            Some(best) if best.1 >= cur.1 => Some(best),
+            _ => Some(cur),
+        });
 
-    // Bruteforce all possibilities
-    // Shortening the call from the back is required to due to calls like UA9ABC where both prefixes U and UA9 a potential matches,
+    // Search for the longest regular prefix
+    // Using the longest one is required due to calls like UA9ABC where both prefixes U and UA9 are potential matches,
     // but the more explicit one is the correct one.
-    let mut prefix: Option<(&Prefix, usize)> = None;
-    for cnt in (1..len_potential_prefix + 1).rev() {
-        // Shortened call
-        let slice = &potential_prefix[0..cnt];
-
-        // Append all single chars to the call as <call>/<appendix> and check if the prefix is valid
-        // This check is required for prefixes like SV/A where the callsign SV1ABC/A shall match too
-        if let Some(pref) = single_char_appendices
-            .iter()
-            .find_map(|a| clublog.get_prefix(&format!("{}/{}", slice, a), timestamp))
-        {
-            prefix = Some((pref, len_potential_prefix - cnt));
-            break;
-        }
-
-        // Check if prefix is valid
-        if let Some(pref) = clublog.get_prefix(slice, timestamp) {
-            prefix = Some((pref, len_potential_prefix - cnt));
-            break;
-        }
-    }
+    let regular = clublog
+        .longest_prefix_match(potential_prefix, timestamp)
+        .map(|p| (p, p.call.len()));
+
+    let prefix = match (special, regular) {
+        (Some(special), Some(regular)) if regular.1 > special.1 => regular,
+        (Some(special), _) => special,
+        (None, Some(regular)) => regular,
+        (None, None) => return None,
+    };
 
-    prefix
+    Some((prefix.0, potential_prefix.len() - prefix.1))
 }
 
 #[cfg(test)]
@@ -560,7 +1074,7 @@ mod tests {
 
     #[test]
     fn clublog_prefix_entity_invalid() {
-        let calls = vec!["X5ABC", "X5ABC/P", "X5/W1AW", "X5/W1AW/P"];
+        let calls = ["X5ABC", "X5ABC/P", "X5/W1AW", "X5/W1AW/P"];
 
         let clublog = read_clublog_xml();
         for call in calls.iter() {
@@ -571,13 +1085,19 @@ mod tests {
                     .unwrap()
                     .into(),
             );
-            assert_eq!(res, Err(CallsignError::BeginWithoutPrefix));
+            assert_eq!(
+                res,
+                Err(CallsignError::BeginWithoutPrefix {
+                    part: String::from(call.split('/').next().unwrap()),
+                    index: 0
+                })
+            );
         }
     }
 
     #[test]
     fn clublog_special_appendix() {
-        let calls = vec![
+        let calls = [
             ("KB5SIW/STS50", "2020-01-01T00:00:00Z"), // test for call exception record 2730
             ("ZY0RK", "1994-08-20T00:00:00Z"),        // test for callsign exception record 28169
         ];
@@ -597,7 +1117,7 @@ mod tests {
 
     #[test]
     fn clublog_whitelist() {
-        let params = vec![
+        let params = [
             ("KH4AB", "1980-04-07T00:00:00Z", true), // Timestamp after start of whitelist and call is part of exception list
             ("KH4AB", "1981-01-01T00:00:00Z", false), // Timestamp after start of whitelist and call not part of exception list
         ];
@@ -610,12 +1130,13 @@ mod tests {
             let call = analyze_callsign(clublog, param.0, timestamp).unwrap();
             let res = check_whitelist(clublog, &call, timestamp);
             assert_eq!(param.2, res);
+            assert_eq!(param.2, !call.whitelist.is_rejected());
         }
     }
 
     #[test]
     fn special_appendix_ok() {
-        let calls = vec![
+        let calls = [
             // AM
             "W1AW/AM",
             "W1AM/P/AM",
@@ -650,7 +1171,7 @@ mod tests {
 
     #[test]
     fn special_appendix_err() {
-        let calls = vec![
+        let calls = [
             // AM
             "W1AW/AM/SAT",
             "W1AM/AM/MM",
@@ -683,13 +1204,32 @@ mod tests {
                     .unwrap()
                     .into(),
             );
-            assert_eq!(res, Err(CallsignError::MultipleSpecialAppendices));
+            // Conflicting special appendices or, if there are none, single digits
+            let appendices: Vec<String> = call
+                .split('/')
+                .skip(1)
+                .filter(|a| ["AM", "MM", "SAT"].contains(a))
+                .map(String::from)
+                .collect();
+            let appendices = match appendices.is_empty() {
+                true => call
+                    .split('/')
+                    .skip(1)
+                    .filter(|a| a.len() == 1 && a.chars().all(|c| c.is_ascii_digit()))
+                    .map(String::from)
+                    .collect(),
+                false => appendices,
+            };
+            assert_eq!(
+                res,
+                Err(CallsignError::MultipleSpecialAppendices { appendices })
+            );
         }
     }
 
     #[test]
     fn special_entity_prefix() {
-        let calls = vec![
+        let calls = [
             ("SV1ABC/A", "2020-01-01T00:00:00Z", 180),    // Prefix SV/A
             ("SV2/W1AW/A", "2020-01-01T00:00:00Z", 180),  // Prefix SV/A
             ("3D2ABC/R", "2020-01-01T00:00:00Z", 460), // Prefix 3D2/R, where 3D2 and R are potential valid prefixes too
@@ -711,7 +1251,7 @@ mod tests {
 
     #[test]
     fn cqzone_exception() {
-        let calls = vec![
+        let calls = [
             ("W1CBY/VE8", "1993-07-01T00:00:00Z", 1), // Record 548
             ("VE2BQB", "1992-01-01T00:00:00Z", 2),    // Record 35
         ];
@@ -731,7 +1271,7 @@ mod tests {
 
     #[test]
     fn call_exceptions() {
-        let calls = vec![
+        let calls = [
             ("AM70URE/8", "2019-05-01T00:00:00Z", 29),
             ("EA8VK/URE", "2021-01-01T00:00:00Z", 29),
         ];
@@ -751,9 +1291,9 @@ mod tests {
 
     #[test]
     fn invalid_operation() {
-        let calls = vec![
-            ("T8T", "1995-05-01T01:00:00Z"),       // record 490
-            ("3D2/N1GXE", "2021-01-01T00:00:00Z"), // record 1155
+        let calls = [
+            ("T8T", "1995-05-01T01:00:00Z", 490),
+            ("3D2/N1GXE", "2021-01-01T00:00:00Z", 1155),
         ];
 
         let clublog = read_clublog_xml();
@@ -764,13 +1304,17 @@ mod tests {
                 call.0,
                 &DateTime::parse_from_rfc3339(call.1).unwrap().into(),
             );
-            assert_eq!(res, Err(CallsignError::InvalidOperation));
+            assert!(
+                matches!(res, Err(CallsignError::InvalidOperation { record: Some(record), .. }) if record == call.2),
+                "{}",
+                call.0
+            );
         }
     }
 
     #[test]
     fn genuine_calls() {
-        let calls = vec![
+        let calls = [
             ("W1ABC", 291),     // Basic call
             ("9A1ABC", 497),    // Call beginning with a number
             ("A71AB", 376),     // Call with two digits, one belonging to the prefix
@@ -806,11 +1350,17 @@ mod tests {
 
     #[test]
     fn invalid_format() {
-        let calls = vec!["W1AW/", "/W1AW", "W1ABC.", "W1ABC/.", "W1<ABC>"];
+        let calls = [
+            ("W1AW/", 4),
+            ("/W1AW", 0),
+            ("W1ABC.", 5),
+            ("W1ABC/.", 6),
+            ("W1<ABC>", 2),
+        ];
 
         let clublog = read_clublog_xml();
 
-        for call in calls.iter() {
+        for (call, position) in calls.iter() {
             let res = analyze_callsign(
                 clublog,
                 call,
@@ -818,17 +1368,22 @@ mod tests {
                     .unwrap()
                     .into(),
             );
-            assert_eq!(res, Err(CallsignError::BasicFormat));
+            assert_eq!(
+                res,
+                Err(CallsignError::BasicFormat {
+                    position: *position
+                })
+            );
         }
     }
 
     #[test]
     fn too_much_prefixes() {
-        let calls = vec!["W/K/W1AW", "W1AW/K/W", "K/W1AW/W"];
+        let calls = [("W/K/W1AW", "W1AW"), ("W1AW/K/W", "W"), ("K/W1AW/W", "W")];
 
         let clublog = read_clublog_xml();
 
-        for call in calls.iter() {
+        for (call, part) in calls.iter() {
             let res = analyze_callsign(
                 clublog,
                 call,
@@ -836,7 +1391,510 @@ mod tests {
                     .unwrap()
                     .into(),
             );
-            assert_eq!(res, Err(CallsignError::TooMuchPrefixes));
+            assert_eq!(
+                res,
+                Err(CallsignError::TooMuchPrefixes {
+                    part: String::from(*part),
+                    index: 2
+                })
+            );
         }
     }
+
+    #[test]
+    fn explain_decisions() {
+        let clublog =
+            ClubLogMap::from(ClubLog::parse(include_str!("../tests/data/cty_sample.xml")).unwrap());
+        let timestamp = DateTime::parse_from_rfc3339("2020-01-01T00:00:00Z")
+            .unwrap()
+            .into();
+        let prefix = |record, call: &str, stripped| PrefixMatch {
+            record,
+            call: String::from(call),
+            stripped,
+        };
+
+        // Double prefix, the one with less removed chars wins
+        let (res, explanation) = analyze_callsign_explained(&clublog, "VE/DL1ABC/P", &timestamp);
+        assert_eq!(res.unwrap().adif, 1);
+        assert_eq!(
+            explanation.parts,
+            vec![
+                Part {
+                    part: String::from("VE"),
+                    parttype: PartType::Prefix,
+                    prefix: Some(prefix(11, "VE", 0)),
+                },
+                Part {
+                    part: String::from("DL1ABC"),
+                    parttype: PartType::Prefix,
+                    prefix: Some(prefix(10, "DL", 4)),
+                },
+                Part {
+                    part: String::from("P"),
+                    parttype: PartType::Other,
+                    prefix: None,
+                },
+            ]
+        );
+        assert_eq!(
+            explanation.states,
+            vec![
+                State::NoPrefix,
+                State::SinglePrefix,
+                State::DoublePrefix,
+                State::PrefixComplete(2)
+            ]
+        );
+        assert_eq!(explanation.prefix, Some(prefix(11, "VE", 0)));
+
+        // Special and single digit appendices
+        let (res, explanation) = analyze_callsign_explained(&clublog, "DL1ABC/MM", &timestamp);
+        assert!(res.unwrap().is_special_entity());
+        assert_eq!(explanation.special_appendix.as_deref(), Some("MM"));
+        assert_eq!(explanation.prefix, None);
+
+        let (_, explanation) = analyze_callsign_explained(&clublog, "DL1ABC/2", &timestamp);
+        assert_eq!(explanation.single_digit_appendix, None);
+        assert_eq!(explanation.prefix, Some(prefix(10, "DL", 4)));
+
+        let mut data = ClubLog::parse(include_str!("../tests/data/cty_sample.xml")).unwrap();
+        let mut ve2 = data.prefixes.list[1].clone();
+        ve2.record = 12;
+        ve2.call = String::from("VE2");
+        data.prefixes.list.push(ve2);
+        let (_, explanation) =
+            analyze_callsign_explained(&ClubLogMap::from(data), "VE1ABC/2", &timestamp);
+        assert_eq!(explanation.single_digit_appendix.as_deref(), Some("2"));
+        assert_eq!(explanation.prefix, Some(prefix(12, "VE2", 3)));
+
+        // Callsign and zone exceptions
+        let (_, explanation) = analyze_callsign_explained(&clublog, "VE1ABC", &timestamp);
+        assert_eq!(explanation.zone_exception, Some(2));
+        assert_eq!(explanation.exception, None);
+
+        let (res, explanation) = analyze_callsign_explained(
+            &clublog,
+            "DL0ABC",
+            &DateTime::parse_from_rfc3339("2000-06-01T00:00:00Z")
+                .unwrap()
+                .into(),
+        );
+        assert_eq!(res.unwrap().adif, 1);
+        assert_eq!(
+            explanation,
+            Explanation {
+                exception: Some(1),
+                ..Default::default()
+            }
+        );
+
+        // Trace is kept on errors
+        let (res, explanation) = analyze_callsign_explained(&clublog, "ABC/DL1ABC", &timestamp);
+        assert_eq!(
+            res,
+            Err(CallsignError::BeginWithoutPrefix {
+                part: String::from("ABC"),
+                index: 0
+            })
+        );
+        assert_eq!(explanation.parts.len(), 2);
+        assert_eq!(explanation.states, vec![State::NoPrefix]);
+
+        // Options apply to the trace as well
+        let options = AnalyzeOptions::default().with_normalization(Normalization::Lenient);
+        let (res, explanation) =
+            analyze_callsign_explained_with(&clublog, "dl1abc/2", &timestamp, &options);
+        assert_eq!(res.unwrap().call, "DL1ABC/2");
+        assert_eq!(explanation.prefix, Some(prefix(10, "DL", 4)));
+    }
+
+    #[test]
+    fn decompose_with_data() {
+        let clublog =
+            ClubLogMap::from(ClubLog::parse(include_str!("../tests/data/cty_sample.xml")).unwrap());
+        let timestamp = DateTime::parse_from_rfc3339("2020-01-01T00:00:00Z")
+            .unwrap()
+            .into();
+
+        let parts = analyze_callsign(&clublog, "DL1ABC/VE/P", &timestamp)
+            .unwrap()
+            .parts;
+        assert_eq!(
+            parts,
+            CallsignParts {
+                homecall: String::from("DL1ABC"),
+                prefix: Some(String::from("VE")),
+                location_prefix: Some(String::from("VE")),
+                numeral: Some(String::from("1")),
+                suffix: Some(String::from("ABC")),
+                appendices: vec![String::from("P")],
+            }
+        );
+
+        let parts = analyze_callsign(&clublog, "VE3ABC/MM", &timestamp)
+            .unwrap()
+            .parts;
+        assert_eq!(parts.homecall, "VE3ABC");
+        assert_eq!(parts.prefix.as_deref(), Some("VE"));
+        assert_eq!(parts.location_prefix, None);
+        assert_eq!(parts.appendices, vec![String::from("MM")]);
+
+        // Callsign exception
+        let parts = analyze_callsign(
+            &clublog,
+            "DL0ABC",
+            &DateTime::parse_from_rfc3339("2000-06-01T00:00:00Z")
+                .unwrap()
+                .into(),
+        )
+        .unwrap()
+        .parts;
+        assert_eq!(parts.homecall, "DL0ABC");
+        assert_eq!(parts.prefix, None);
+    }
+
+    #[test]
+    fn alternatives_ranked() {
+        let special = r#"<prefix record="14"><call>DL/A</call><entity>CANADA</entity><adif>1</adif></prefix>
+<prefix record="15"><call>3D2</call><entity>FIJI</entity><adif>176</adif></prefix>
+<prefix record="16"><call>3D2/R</call><entity>ROTUMA ISLAND</entity><adif>460</adif></prefix>
+</prefixes>"#;
+        let clublog = ClubLogMap::from(
+            ClubLog::parse(
+                &include_str!("../tests/data/cty_sample.xml").replace("</prefixes>", special),
+            )
+            .unwrap(),
+        );
+        let timestamp = DateTime::parse_from_rfc3339("2020-01-01T00:00:00Z")
+            .unwrap()
+            .into();
+        let summary = |call| {
+            analyze_callsign_alternatives(&clublog, call, &timestamp).map(|alternatives| {
+                alternatives
+                    .iter()
+                    .map(|i| (i.callsign.adif, (i.confidence * 100.0).round(), i.rule))
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        // Two valid prefixes, the one with less removed chars is ranked first
+        assert_eq!(
+            summary("VE/DL1ABC/P"),
+            Ok(vec![
+                (1, 83.0, Rule::DoublePrefix(0)),
+                (230, 17.0, Rule::DoublePrefix(1))
+            ])
+        );
+        assert_eq!(
+            summary("DL1ABC/VE"),
+            Ok(vec![
+                (1, 83.0, Rule::DoublePrefix(1)),
+                (230, 17.0, Rule::DoublePrefix(0))
+            ])
+        );
+
+        // Special prefix or regular prefix
+        assert_eq!(
+            summary("DL1ABC/A"),
+            Ok(vec![
+                (1, 50.0, Rule::SpecialPrefix),
+                (230, 50.0, Rule::HomecallPrefix)
+            ])
+        );
+        assert_eq!(
+            summary("3D2ABC/R"),
+            Ok(vec![
+                (460, 50.0, Rule::SpecialPrefix),
+                (176, 50.0, Rule::HomecallPrefix)
+            ])
+        );
+
+        // Special prefix matched by the location prefix
+        assert_eq!(
+            summary("3D2/DL1ABC/R"),
+            Ok(vec![
+                (460, 50.0, Rule::SpecialPrefix),
+                (176, 50.0, Rule::DoublePrefix(0))
+            ])
+        );
+        let interpretations =
+            analyze_callsign_alternatives(&clublog, "3D2/DL1ABC/R", &timestamp).unwrap();
+        assert_eq!(interpretations[1].callsign.parts.homecall, "DL1ABC");
+        assert_eq!(
+            interpretations[1].callsign.parts.location_prefix.as_deref(),
+            Some("3D2")
+        );
+
+        // Options of the analysis apply to the alternatives as well
+        let options = AnalyzeOptions::default()
+            .with_single_letter(SingleLetterPolicy::Ignore)
+            .with_normalization(Normalization::Lenient);
+        let interpretations =
+            analyze_callsign_alternatives_with(&clublog, "dl1abc/a", &timestamp, &options).unwrap();
+        assert_eq!(interpretations.len(), 1);
+        assert_eq!(interpretations[0].callsign.call, "DL1ABC/A");
+        assert_eq!(interpretations[0].callsign.adif, 230);
+        assert_eq!(
+            summary("VE/DL1ABC/A"),
+            Ok(vec![(1, 100.0, Rule::DoublePrefix(0))])
+        );
+        let interpretations =
+            analyze_callsign_alternatives_with(&clublog, "VE/DL1ABC/A", &timestamp, &options)
+                .unwrap();
+        let alternatives: Vec<_> = interpretations
+            .iter()
+            .map(|i| (i.callsign.adif, i.rule))
+            .collect();
+        assert_eq!(
+            alternatives,
+            vec![(1, Rule::DoublePrefix(0)), (230, Rule::DoublePrefix(1))]
+        );
+
+        // Policy by the entity of the homecall
+        let options =
+            AnalyzeOptions::default().with_entity_single_letter(230, SingleLetterPolicy::Ignore);
+        let interpretations =
+            analyze_callsign_alternatives_with(&clublog, "VE/DL1ABC/A", &timestamp, &options)
+                .unwrap();
+        assert_eq!(interpretations[1].callsign.adif, 230);
+
+        // Same entity or no alternatives at all
+        assert_eq!(
+            summary("VE/VE1ABC"),
+            Ok(vec![(1, 100.0, Rule::DoublePrefix(0))])
+        );
+        assert_eq!(
+            summary("DL1ABC/MM"),
+            Ok(vec![(0, 100.0, Rule::SpecialAppendix)])
+        );
+        assert_eq!(
+            summary("DL1ABC"),
+            Ok(vec![(230, 100.0, Rule::HomecallPrefix)])
+        );
+        assert_eq!(
+            summary("ABC"),
+            Err(CallsignError::BeginWithoutPrefix {
+                part: String::from("ABC"),
+                index: 0
+            })
+        );
+    }
+
+    #[test]
+    fn single_letter_policy() {
+        let prefixes = r#"<prefix record="14"><call>DL/A</call><entity>CANADA</entity><adif>1</adif></prefix>
+<prefix record="15"><call>LU</call><entity>ARGENTINA</entity><adif>100</adif></prefix>
+<prefix record="16"><call>F</call><entity>FRANCE</entity><adif>227</adif></prefix>
+</prefixes>"#;
+        let clublog = ClubLogMap::from(
+            ClubLog::parse(
+                &include_str!("../tests/data/cty_sample.xml").replace("</prefixes>", prefixes),
+            )
+            .unwrap(),
+        );
+        let timestamp = DateTime::parse_from_rfc3339("2020-01-01T00:00:00Z")
+            .unwrap()
+            .into();
+        let adif = |call, options: &AnalyzeOptions| {
+            analyze_callsign_with(&clublog, call, &timestamp, options).map(|c| c.adif)
+        };
+
+        let options = AnalyzeOptions::default();
+        assert_eq!(adif("DL1ABC/A", &options), Ok(1));
+        assert_eq!(adif("LU1ABC/F", &options), Ok(227));
+        assert_eq!(adif("DL1ABC/F", &options), Ok(227));
+
+        let options =
+            AnalyzeOptions::default().with_single_letter(SingleLetterPolicy::SpecialPrefix);
+        assert_eq!(adif("DL1ABC/A", &options), Ok(1));
+        assert_eq!(adif("LU1ABC/F", &options), Ok(100));
+
+        let options =
+            AnalyzeOptions::default().with_single_letter(SingleLetterPolicy::StandalonePrefix);
+        assert_eq!(adif("DL1ABC/A", &options), Ok(230));
+        assert_eq!(adif("LU1ABC/F", &options), Ok(227));
+
+        // Single letters are area designators for Argentinian callsigns only
+        let options =
+            AnalyzeOptions::default().with_entity_single_letter(100, SingleLetterPolicy::Ignore);
+        assert_eq!(adif("LU1ABC/F", &options), Ok(100));
+        assert_eq!(adif("F/LU1ABC", &options), Ok(227));
+        assert_eq!(adif("DL1ABC/F", &options), Ok(227));
+        assert_eq!(
+            analyze_callsign_with(&clublog, "LU1ABC/F", &timestamp, &options)
+                .unwrap()
+                .parts
+                .appendices,
+            vec![String::from("F")]
+        );
+    }
+
+    #[test]
+    fn lenient_normalization() {
+        let clublog =
+            ClubLogMap::from(ClubLog::parse(include_str!("../tests/data/cty_sample.xml")).unwrap());
+        let timestamp = DateTime::parse_from_rfc3339("2020-01-01T00:00:00Z")
+            .unwrap()
+            .into();
+        let options = AnalyzeOptions::default().with_normalization(Normalization::Lenient);
+
+        assert_eq!(
+            analyze_callsign(&clublog, " ve/dlØabc ", &timestamp),
+            Err(CallsignError::BasicFormat { position: 0 })
+        );
+        assert_eq!(
+            analyze_callsign(&clublog, "VE//DL1ABC", &timestamp),
+            Err(CallsignError::BasicFormat { position: 3 })
+        );
+
+        let (res, explanation) =
+            RuleChain::default().analyze_explained(&clublog, " ve//dlØabc ", &timestamp, &options);
+        let res = res.unwrap();
+        assert_eq!(res.call, "VE/DL0ABC");
+        assert_eq!(res.adif, 1);
+        assert_eq!(
+            explanation.fixes,
+            vec![
+                Fix::Trimmed,
+                Fix::Uppercased,
+                Fix::LookAlike {
+                    from: 'Ø', to: '0'
+                },
+                Fix::RepeatedSeparator
+            ]
+        );
+        assert_eq!(
+            analyze_callsign_with(&clublog, "w1aw/", &timestamp, &options),
+            Err(CallsignError::BasicFormat { position: 4 })
+        );
+    }
+
+    #[test]
+    fn error_context() {
+        // Enable whitelisting for Canada beginning with 2010
+        let clublog = ClubLogMap::from(
+            ClubLog::parse(&include_str!("../tests/data/cty_sample.xml").replace(
+                "<lat>45.0</lat></entity>",
+                "<lat>45.0</lat><whitelist>true</whitelist><whitelist_start>2010-01-01T00:00:00+00:00</whitelist_start></entity>",
+            ))
+            .unwrap(),
+        );
+        let timestamp = |ts| DateTime::parse_from_rfc3339(ts).unwrap().into();
+
+        let err = analyze_callsign(&clublog, "DL0XX", &timestamp("2001-06-01T00:00:00Z"));
+        assert_eq!(
+            err,
+            Err(CallsignError::InvalidOperation {
+                record: Some(20),
+                start: Some(timestamp("2001-01-01T00:00:00Z")),
+                end: Some(timestamp("2001-12-31T23:59:59Z"))
+            })
+        );
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "Callsign was used in an invalid operation (record 20, 2001-01-01T00:00:00+00:00 - 2001-12-31T23:59:59+00:00)"
+        );
+
+        let ts = timestamp("2020-01-01T00:00:00Z");
+        assert_eq!(
+            analyze_callsign(&clublog, "DL1ABC/VE/DL", &ts),
+            Err(CallsignError::TooMuchPrefixes {
+                part: String::from("DL"),
+                index: 2
+            })
+        );
+        assert_eq!(
+            analyze_callsign(&clublog, "DL1ABC/MM/AM", &ts)
+                .unwrap_err()
+                .to_string(),
+            "Multiple special appendices MM, AM"
+        );
+        assert_eq!(
+            analyze_callsign(&clublog, "XX1ABC/P", &ts)
+                .unwrap_err()
+                .to_string(),
+            "Callsign does not begin with a valid prefix, part XX1ABC at index 0 is no prefix"
+        );
+
+        // Syntactically fine, but not whitelisted
+        let call = analyze_callsign(&clublog, "VE3XYZ", &ts).unwrap();
+        assert!(!check_whitelist(&clublog, &call, &ts));
+        assert_eq!(
+            verify_whitelist(&clublog, &call, &ts),
+            Err(CallsignError::NotWhitelisted {
+                adif: 1,
+                entity: String::from("CANADA")
+            })
+        );
+        let ts = timestamp("2005-01-01T00:00:00Z");
+        assert_eq!(verify_whitelist(&clublog, &call, &ts), Ok(()));
+        let ts = timestamp("2000-06-01T00:00:00Z");
+        let call = analyze_callsign(&clublog, "DL0ABC", &ts).unwrap();
+        assert_eq!(verify_whitelist(&clublog, &call, &ts), Ok(()));
+    }
+
+    #[test]
+    fn whitelist_in_result() {
+        // Enable whitelisting for Canada from 2010 to 2029 and whitelist VE9XX
+        let clublog = ClubLogMap::from(
+            ClubLog::parse(&include_str!("../tests/data/cty_sample.xml").replace(
+                "<lat>45.0</lat></entity>",
+                "<lat>45.0</lat><whitelist>true</whitelist><whitelist_start>2010-01-01T00:00:00+00:00</whitelist_start><whitelist_end>2029-12-31T23:59:59+00:00</whitelist_end></entity>",
+            ).replace(
+                "</exceptions>",
+                "<exception record=\"2\"><call>VE9XX</call><entity>CANADA</entity><adif>1</adif></exception></exceptions>",
+            ))
+            .unwrap(),
+        );
+        let timestamp = |ts| DateTime::parse_from_rfc3339(ts).unwrap().into();
+        let start = Some(timestamp("2010-01-01T00:00:00Z"));
+        let end = Some(timestamp("2029-12-31T23:59:59Z"));
+        let status = |call, ts| {
+            analyze_callsign(&clublog, call, &timestamp(ts))
+                .unwrap()
+                .whitelist
+        };
+
+        assert_eq!(
+            status("DL1ABC", "2020-01-01T00:00:00Z"),
+            WhitelistStatus::NotApplicable
+        );
+        assert_eq!(
+            status("VE9XX", "2020-01-01T00:00:00Z"),
+            WhitelistStatus::Whitelisted {
+                record: 2,
+                start,
+                end
+            }
+        );
+        assert_eq!(
+            status("VE3XYZ", "2020-01-01T00:00:00Z"),
+            WhitelistStatus::Rejected {
+                record: None,
+                start,
+                end
+            }
+        );
+        for ts in ["2005-01-01T00:00:00Z", "2030-01-01T00:00:00Z"] {
+            assert_eq!(
+                status("VE3XYZ", ts),
+                WhitelistStatus::OutsideWindow { start, end }
+            );
+        }
+
+        // The status is also available for alternative interpretations
+        let interpretations = analyze_callsign_alternatives(
+            &clublog,
+            "DL/VE3XYZ",
+            &timestamp("2020-01-01T00:00:00Z"),
+        )
+        .unwrap();
+        assert_eq!(interpretations.len(), 2);
+        assert_eq!(
+            interpretations[0].callsign.whitelist,
+            WhitelistStatus::NotApplicable
+        );
+        assert!(interpretations[1].callsign.whitelist.is_rejected());
+    }
 }
@@ -0,0 +1,710 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Callsign analyzer with a cache for the analysis results.
+//!
+//! Contest or cluster applications analyze the same callsigns over and over again.
+//! The [CallsignAnalyzer] keeps the results of [analyze_callsign](crate::call::analyze_callsign) in a least recently used cache.
+//!
+//! The result of an analysis does not only depend on the callsign, but also on the timestamp, since all records of the ClubLog data are bound to time windows.
+//! While analyzing a callsign, the time windows of all records that were queried are collected, even the ones of records not valid at the timestamp.
+//! This results in the validity interval around the timestamp in which none of the queried records changes its validity.
+//! Therefore the cached result is reused for all timestamps within this interval.
+//! The time windows are taken from the history methods of [ClubLogQuery], so results are only cached if the data [supports them](ClubLogQuery::supports_history).
+
+use crate::call::{analyze_callsign_with, AnalyzeOptions, Callsign, CallsignError};
+use crate::clublog::{
+    Adif, CallsignException, CqZone, Entity, InvalidOperation, Prefix, ZoneException,
+};
+use crate::clublogquery::{ClubLogQuery, QueryIter};
+use chrono::{DateTime, Duration, Utc};
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
+
+/// Default maximum number of cached results
+pub const DEFAULT_CAPACITY: usize = 10000;
+
+/// Statistics of the cache
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct CacheStats {
+    /// Number of analyses answered by the cache
+    pub hits: u64,
+    /// Number of analyses that were not cached
+    pub misses: u64,
+    /// Number of results removed from the cache due to the size limit
+    pub evictions: u64,
+    /// Number of results currently cached
+    pub entries: usize,
+}
+
+/// Time interval, where the start is inclusive and the end is exclusive.
+/// A missing start or end is interpreted as unlimited.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+struct Interval {
+    /// Start of the interval
+    start: Option<DateTime<Utc>>,
+    /// End of the interval
+    end: Option<DateTime<Utc>>,
+}
+
+impl Interval {
+    /// Check whether a timestamp is within the interval.
+    ///
+    /// # Arguments
+    ///
+    /// - `timestamp`: Timestamp to check
+    ///
+    /// # Returns
+    ///
+    /// True if the timestamp is within the interval, false otherwise
+    fn contains(&self, timestamp: &DateTime<Utc>) -> bool {
+        !matches!(self.start, Some(s) if *timestamp < s)
+            && !matches!(self.end, Some(e) if *timestamp >= e)
+    }
+
+    /// Shrink the interval around a timestamp, so that the validity of a record does not change within the interval.
+    ///
+    /// # Arguments
+    ///
+    /// - `timestamp`: Timestamp the interval is built around
+    /// - `start`: Start timestamp of the record
+    /// - `end`: End timestamp of the record
+    ///
+    /// # Returns
+    ///
+    /// (None)
+    fn narrow(
+        &mut self,
+        timestamp: &DateTime<Utc>,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) {
+        // The validity of a record changes at its start and right after its end
+        let boundaries = [start, end.map(|e| e + Duration::nanoseconds(1))];
+
+        for boundary in boundaries.into_iter().flatten() {
+            if boundary <= *timestamp {
+                self.start = Some(self.start.map_or(boundary, |s| s.max(boundary)));
+            } else {
+                self.end = Some(self.end.map_or(boundary, |e| e.min(boundary)));
+            }
+        }
+    }
+}
+
+/// Cached result of an analysis
+#[derive(Debug)]
+struct CacheEntry {
+    /// Interval in which the result is valid
+    interval: Interval,
+    /// Result of the analysis
+    result: Result<Callsign, CallsignError>,
+    /// Value of the usage counter at the last access
+    tick: u64,
+}
+
+/// Callsign analyzer that caches the analysis results
+#[derive(Debug)]
+pub struct CallsignAnalyzer<Q> {
+    /// ClubLog data
+    data: Q,
+    /// Maximum number of cached results
+    capacity: usize,
+    /// Options of the analysis
+    options: AnalyzeOptions,
+    /// Cached results by callsign
+    entries: HashMap<String, Vec<CacheEntry>>,
+    /// Callsign of the cached results by their last access, used to find the least recently used result
+    usage: BTreeMap<u64, String>,
+    /// Usage counter, increased on every access
+    tick: u64,
+    /// Statistics
+    stats: CacheStats,
+}
+
+impl<Q: ClubLogQuery> CallsignAnalyzer<Q> {
+    /// Create a new analyzer with the [default capacity](DEFAULT_CAPACITY).
+    ///
+    /// # Arguments
+    ///
+    /// - `data`: ClubLog data
+    ///
+    /// # Returns
+    ///
+    /// Analyzer
+    pub fn new(data: Q) -> Self {
+        CallsignAnalyzer::with_capacity(data, DEFAULT_CAPACITY)
+    }
+
+    /// Create a new analyzer.
+    ///
+    /// # Arguments
+    ///
+    /// - `data`: ClubLog data
+    /// - `capacity`: Maximum number of cached results, zero disables the cache
+    ///
+    /// # Returns
+    ///
+    /// Analyzer
+    pub fn with_capacity(data: Q, capacity: usize) -> Self {
+        CallsignAnalyzer {
+            data,
+            capacity,
+            options: AnalyzeOptions::default(),
+            entries: HashMap::new(),
+            usage: BTreeMap::new(),
+            tick: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Set the options of the analysis, e.g. to normalize hand-typed callsigns.
+    /// Since the cached results depend on the options, the cache is cleared.
+    ///
+    /// # Arguments
+    ///
+    /// - `options`: Options of the analysis
+    ///
+    /// # Returns
+    ///
+    /// Analyzer
+    pub fn with_options(mut self, options: AnalyzeOptions) -> Self {
+        self.options = options;
+        self.clear();
+        self
+    }
+
+    /// Get the ClubLog data.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// ClubLog data
+    pub fn data(&self) -> &Q {
+        &self.data
+    }
+
+    /// Get the statistics of the cache.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Statistics
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Remove all cached results.
+    /// The statistics, except for the number of cached results, are kept.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// (None)
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.usage.clear();
+        self.stats.entries = 0;
+    }
+
+    /// Analyze a callsign like [analyze_callsign_with] does with the [options](Self::with_options) of the analyzer, but answer from the cache if possible.
+    ///
+    /// # Arguments
+    ///
+    /// - `call`: Callsign to analyze
+    /// - `timestamp`: Timestamp to use for the check
+    ///
+    /// # Returns
+    ///
+    /// Returns further information about the callsign or an error.
+    pub fn analyze(
+        &mut self,
+        call: &str,
+        timestamp: &DateTime<Utc>,
+    ) -> Result<Callsign, CallsignError> {
+        self.tick += 1;
+
+        if let Some(entry) = self
+            .entries
+            .get_mut(call)
+            .and_then(|e| e.iter_mut().find(|e| e.interval.contains(timestamp)))
+        {
+            self.usage.remove(&entry.tick);
+            self.usage.insert(self.tick, String::from(call));
+            entry.tick = self.tick;
+            self.stats.hits += 1;
+            return entry.result.clone();
+        }
+        self.stats.misses += 1;
+
+        let recorder = Recorder {
+            data: &self.data,
+            timestamp: *timestamp,
+            interval: Cell::new(Interval::default()),
+        };
+        let result = analyze_callsign_with(&recorder, call, timestamp, &self.options);
+        let interval = recorder.interval.get();
+
+        // Without the history of the records the interval is unknown
+        if self.capacity > 0 && self.data.supports_history() {
+            while self.stats.entries >= self.capacity {
+                self.evict();
+            }
+
+            self.entries
+                .entry(String::from(call))
+                .or_default()
+                .push(CacheEntry {
+                    interval,
+                    result: result.clone(),
+                    tick: self.tick,
+                });
+            self.usage.insert(self.tick, String::from(call));
+            self.stats.entries += 1;
+        }
+
+        result
+    }
+
+    /// Remove the least recently used result from the cache.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// (None)
+    fn evict(&mut self) {
+        if let Some((tick, call)) = self.usage.pop_first() {
+            if let Some(entries) = self.entries.get_mut(&call) {
+                entries.retain(|e| e.tick != tick);
+                if entries.is_empty() {
+                    self.entries.remove(&call);
+                }
+            }
+            self.stats.entries -= 1;
+            self.stats.evictions += 1;
+        }
+    }
+}
+
+/// Wrapper of the ClubLog data that collects the time windows of all records relevant for the queries
+struct Recorder<'a, Q> {
+    /// ClubLog data
+    data: &'a Q,
+    /// Timestamp of the analysis
+    timestamp: DateTime<Utc>,
+    /// Interval around the timestamp in which the answers of all queries so far stay the same
+    interval: Cell<Interval>,
+}
+
+impl<Q: ClubLogQuery> Recorder<'_, Q> {
+    /// Shrink the validity interval by the time windows of records.
+    ///
+    /// # Arguments
+    ///
+    /// - `windows`: Start and end timestamps of the records
+    ///
+    /// # Returns
+    ///
+    /// (None)
+    fn narrow<I>(&self, windows: I)
+    where
+        I: IntoIterator<Item = (Option<DateTime<Utc>>, Option<DateTime<Utc>>)>,
+    {
+        let mut interval = self.interval.get();
+        for (start, end) in windows {
+            interval.narrow(&self.timestamp, start, end);
+        }
+        self.interval.set(interval);
+    }
+
+    /// Shrink the validity interval by the time windows of all records of a prefix.
+    ///
+    /// # Arguments
+    ///
+    /// - `prefix`: Prefix
+    ///
+    /// # Returns
+    ///
+    /// (None)
+    fn narrow_prefix(&self, prefix: &str) {
+        self.narrow(
+            self.data
+                .prefix_history(prefix)
+                .iter()
+                .map(|p| (p.start, p.end)),
+        );
+    }
+}
+
+impl<Q: ClubLogQuery> ClubLogQuery for Recorder<'_, Q> {
+    fn get_entity(&self, adif: Adif, timestamp: &DateTime<Utc>) -> Option<&Entity> {
+        let entity = self.data.get_entity(adif, timestamp);
+        match entity {
+            Some(entity) => {
+                // The whitelist status of the result changes at the bounds of the whitelist window
+                self.narrow([
+                    (entity.start, entity.end),
+                    (entity.whitelist_start, entity.whitelist_end),
+                ]);
+            }
+            None => {
+                // Another record of the entity may become valid outside of the interval
+                self.narrow(
+                    self.data
+                        .iter_entities()
+                        .filter(|e| e.adif == adif)
+                        .map(|e| (e.start, e.end)),
+                );
+            }
+        }
+        entity
+    }
+
+    fn get_prefix(&self, prefix: &str, timestamp: &DateTime<Utc>) -> Option<&Prefix> {
+        self.narrow_prefix(prefix);
+        self.data.get_prefix(prefix, timestamp)
+    }
+
+    fn longest_prefix_match(&self, call: &str, timestamp: &DateTime<Utc>) -> Option<&Prefix> {
+        for len in (1..=call.len()).filter(|len| call.is_char_boundary(*len)) {
+            self.narrow_prefix(&call[..len]);
+        }
+        self.data.longest_prefix_match(call, timestamp)
+    }
+
+    fn longest_prefix_match_with_appendix(
+        &self,
+        call: &str,
+        appendix: &str,
+        timestamp: &DateTime<Utc>,
+    ) -> Option<&Prefix> {
+        for len in (1..=call.len()).filter(|len| call.is_char_boundary(*len)) {
+            self.narrow_prefix(&format!("{}/{}", &call[..len], appendix));
+        }
+        self.data
+            .longest_prefix_match_with_appendix(call, appendix, timestamp)
+    }
+
+    fn get_callsign_exception(
+        &self,
+        callsign: &str,
+        timestamp: &DateTime<Utc>,
+    ) -> Option<&CallsignException> {
+        self.narrow(
+            self.data
+                .callsign_exception_history(callsign)
+                .iter()
+                .map(|e| (e.start, e.end)),
+        );
+        self.data.get_callsign_exception(callsign, timestamp)
+    }
+
+    fn get_zone_exception(&self, callsign: &str, timestamp: &DateTime<Utc>) -> Option<CqZone> {
+        self.narrow(
+            self.data
+                .zone_exception_history(callsign)
+                .iter()
+                .map(|z| (z.start, z.end)),
+        );
+        self.data.get_zone_exception(callsign, timestamp)
+    }
+
+    fn is_invalid_operation(&self, callsign: &str, timestamp: &DateTime<Utc>) -> bool {
+        self.narrow(
+            self.data
+                .invalid_operation_history(callsign)
+                .iter()
+                .map(|o| (o.start, o.end)),
+        );
+        self.data.is_invalid_operation(callsign, timestamp)
+    }
+
+    fn iter_entities(&self) -> QueryIter<'_, Entity> {
+        self.data.iter_entities()
+    }
+
+    fn iter_prefixes(&self) -> QueryIter<'_, Prefix> {
+        self.data.iter_prefixes()
+    }
+
+    fn iter_callsign_exceptions(&self) -> QueryIter<'_, CallsignException> {
+        self.data.iter_callsign_exceptions()
+    }
+
+    fn iter_zone_exceptions(&self) -> QueryIter<'_, ZoneException> {
+        self.data.iter_zone_exceptions()
+    }
+
+    fn iter_invalid_operations(&self) -> QueryIter<'_, InvalidOperation> {
+        self.data.iter_invalid_operations()
+    }
+
+    fn supports_history(&self) -> bool {
+        self.data.supports_history()
+    }
+
+    fn get_entity_by_prefix(&self, prefix: &str, timestamp: &DateTime<Utc>) -> Option<&Entity> {
+        let entity = self.data.get_entity_by_prefix(prefix, timestamp);
+        if let Some(entity) = entity {
+            self.narrow([(entity.start, entity.end)]);
+        }
+        entity
+    }
+
+    fn entities(&self, timestamp: &DateTime<Utc>) -> QueryIter<'_, Entity> {
+        self.data.entities(timestamp)
+    }
+
+    fn search_entities<'a>(&'a self, name: &str) -> QueryIter<'a, Entity> {
+        self.data.search_entities(name)
+    }
+
+    fn prefixes_by_adif(&self, adif: Adif) -> QueryIter<'_, Prefix> {
+        self.data.prefixes_by_adif(adif)
+    }
+
+    fn exceptions_by_adif(&self, adif: Adif) -> QueryIter<'_, CallsignException> {
+        self.data.exceptions_by_adif(adif)
+    }
+
+    fn prefix_history(&self, prefix: &str) -> Vec<&Prefix> {
+        self.data.prefix_history(prefix)
+    }
+
+    fn callsign_exception_history(&self, callsign: &str) -> Vec<&CallsignException> {
+        self.data.callsign_exception_history(callsign)
+    }
+
+    fn zone_exception_history(&self, callsign: &str) -> Vec<&ZoneException> {
+        self.data.zone_exception_history(callsign)
+    }
+
+    fn invalid_operation_history(&self, callsign: &str) -> Vec<&InvalidOperation> {
+        self.data.invalid_operation_history(callsign)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::call::analyze_callsign;
+    use crate::callnormalize::Normalization;
+    use crate::clublog::ClubLog;
+    use crate::clublogmap::ClubLogMap;
+
+    const SAMPLE: &str = include_str!("../tests/data/cty_sample.xml");
+
+    fn timestamp(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().into()
+    }
+
+    #[test]
+    fn cache_hits_within_interval() {
+        let mut analyzer = CallsignAnalyzer::new(ClubLogMap::from(ClubLog::parse(SAMPLE).unwrap()));
+
+        // Callsign exception for DL0ABC is valid during 2000
+        let ts = timestamp("2000-06-01T00:00:00Z");
+        assert_eq!(analyzer.analyze("DL0ABC", &ts).unwrap().adif, 1);
+        assert_eq!(
+            analyzer
+                .analyze("DL0ABC", &timestamp("2000-12-31T23:59:59Z"))
+                .unwrap()
+                .adif,
+            1
+        );
+        assert_eq!(
+            analyzer.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                evictions: 0,
+                entries: 1
+            }
+        );
+
+        // Outside of the exception the prefix applies
+        let ts = timestamp("2001-01-01T00:00:00Z");
+        assert_eq!(analyzer.analyze("DL0ABC", &ts).unwrap().adif, 230);
+        assert_eq!(analyzer.analyze("DL0ABC", &ts).unwrap().adif, 230);
+        assert_eq!(analyzer.stats().misses, 2);
+        assert_eq!(analyzer.stats().hits, 2);
+
+        // Invalid operation of DL0XX is valid during 2001
+        let ts = timestamp("2001-06-01T00:00:00Z");
+        assert_eq!(
+            analyzer.analyze("DL0XX", &ts),
+            Err(CallsignError::InvalidOperation {
+                record: Some(20),
+                start: Some(timestamp("2001-01-01T00:00:00Z")),
+                end: Some(timestamp("2001-12-31T23:59:59Z"))
+            })
+        );
+        assert_eq!(
+            analyzer.analyze("DL0XX", &timestamp("2002-06-01T00:00:00Z")),
+            analyze_callsign(analyzer.data(), "DL0XX", &timestamp("2002-06-01T00:00:00Z"))
+        );
+        assert_eq!(analyzer.stats().misses, 4);
+    }
+
+    #[test]
+    fn cache_respects_whitelist_window() {
+        // Enable whitelisting for Canada beginning with 2010
+        let clublog = ClubLog::parse(&SAMPLE.replace(
+            "<lat>45.0</lat></entity>",
+            "<lat>45.0</lat><whitelist>true</whitelist><whitelist_start>2010-01-01T00:00:00+00:00</whitelist_start></entity>",
+        ))
+        .unwrap();
+        let mut analyzer = CallsignAnalyzer::new(ClubLogMap::from(clublog));
+
+        let before = analyzer.analyze("VE3XYZ", &timestamp("2009-06-01T00:00:00Z"));
+        assert!(!before.unwrap().whitelist.is_rejected());
+        let after = analyzer.analyze("VE3XYZ", &timestamp("2010-06-01T00:00:00Z"));
+        assert!(after.unwrap().whitelist.is_rejected());
+        assert_eq!(analyzer.stats().hits, 0);
+    }
+
+    #[test]
+    fn cache_respects_missing_entity() {
+        // The whitelisted entity Canada is only valid beginning with 2010
+        let clublog = ClubLog::parse(&SAMPLE.replace(
+            "<lat>45.0</lat></entity>",
+            "<lat>45.0</lat><start>2010-01-01T00:00:00+00:00</start><whitelist>true</whitelist></entity>",
+        ))
+        .unwrap();
+        let mut analyzer = CallsignAnalyzer::new(ClubLogMap::from(clublog));
+
+        let before = analyzer.analyze("VE3XYZ", &timestamp("2009-06-01T00:00:00Z"));
+        assert!(!before.unwrap().whitelist.is_rejected());
+        let after = analyzer.analyze("VE3XYZ", &timestamp("2010-06-01T00:00:00Z"));
+        assert!(after.unwrap().whitelist.is_rejected());
+        assert_eq!(analyzer.stats().hits, 0);
+    }
+
+    #[test]
+    fn cache_eviction() {
+        let mut analyzer =
+            CallsignAnalyzer::with_capacity(ClubLogMap::from(ClubLog::parse(SAMPLE).unwrap()), 2);
+        let ts = timestamp("2020-01-01T00:00:00Z");
+
+        analyzer.analyze("DL1ABC", &ts).unwrap();
+        analyzer.analyze("DL2ABC", &ts).unwrap();
+        analyzer.analyze("DL1ABC", &ts).unwrap();
+        analyzer.analyze("DL3ABC", &ts).unwrap();
+
+        // DL2ABC was the least recently used one
+        assert_eq!(analyzer.stats().evictions, 1);
+        assert_eq!(analyzer.stats().entries, 2);
+        analyzer.analyze("DL1ABC", &ts).unwrap();
+        assert_eq!(analyzer.stats().hits, 2);
+        analyzer.analyze("DL2ABC", &ts).unwrap();
+        assert_eq!(analyzer.stats().misses, 4);
+
+        analyzer.clear();
+        assert_eq!(analyzer.stats().entries, 0);
+    }
+
+    #[test]
+    fn analyze_with_options() {
+        let data = ClubLogMap::from(ClubLog::parse(SAMPLE).unwrap());
+        let ts = timestamp("2020-01-01T00:00:00Z");
+
+        let mut analyzer = CallsignAnalyzer::new(data);
+        assert!(matches!(
+            analyzer.analyze("dl1abc/p", &ts),
+            Err(CallsignError::BasicFormat { .. })
+        ));
+
+        let options = AnalyzeOptions::default().with_normalization(Normalization::Lenient);
+        let mut analyzer = analyzer.with_options(options);
+        assert_eq!(analyzer.stats().entries, 0);
+        let callsign = analyzer.analyze("dl1abc/p", &ts).unwrap();
+        assert_eq!(callsign.call, "DL1ABC/P");
+        assert_eq!(callsign.adif, 230);
+        assert_eq!(analyzer.analyze("dl1abc/p", &ts), Ok(callsign));
+        assert_eq!(analyzer.stats().hits, 1);
+    }
+
+    /// Data answering point lookups only, without listing its records
+    struct PointLookup(ClubLogMap);
+
+    impl ClubLogQuery for PointLookup {
+        fn get_entity(&self, adif: Adif, timestamp: &DateTime<Utc>) -> Option<&Entity> {
+            self.0.get_entity(adif, timestamp)
+        }
+
+        fn get_prefix(&self, prefix: &str, timestamp: &DateTime<Utc>) -> Option<&Prefix> {
+            self.0.get_prefix(prefix, timestamp)
+        }
+
+        fn get_callsign_exception(
+            &self,
+            callsign: &str,
+            timestamp: &DateTime<Utc>,
+        ) -> Option<&CallsignException> {
+            self.0.get_callsign_exception(callsign, timestamp)
+        }
+
+        fn get_zone_exception(&self, callsign: &str, timestamp: &DateTime<Utc>) -> Option<CqZone> {
+            self.0.get_zone_exception(callsign, timestamp)
+        }
+
+        fn is_invalid_operation(&self, callsign: &str, timestamp: &DateTime<Utc>) -> bool {
+            self.0.is_invalid_operation(callsign, timestamp)
+        }
+
+        fn iter_entities(&self) -> QueryIter<'_, Entity> {
+            Box::new(std::iter::empty())
+        }
+
+        fn iter_prefixes(&self) -> QueryIter<'_, Prefix> {
+            Box::new(std::iter::empty())
+        }
+
+        fn iter_callsign_exceptions(&self) -> QueryIter<'_, CallsignException> {
+            Box::new(std::iter::empty())
+        }
+
+        fn iter_zone_exceptions(&self) -> QueryIter<'_, ZoneException> {
+            Box::new(std::iter::empty())
+        }
+
+        fn iter_invalid_operations(&self) -> QueryIter<'_, InvalidOperation> {
+            Box::new(std::iter::empty())
+        }
+    }
+
+    #[test]
+    fn no_cache_without_history() {
+        let mut analyzer = CallsignAnalyzer::new(PointLookup(ClubLogMap::from(
+            ClubLog::parse(SAMPLE).unwrap(),
+        )));
+
+        // Callsign exception for DL0ABC is valid during 2000 only
+        let during = analyzer.analyze("DL0ABC", &timestamp("2000-06-01T00:00:00Z"));
+        assert_eq!(during.unwrap().adif, 1);
+        let after = analyzer.analyze("DL0ABC", &timestamp("2001-06-01T00:00:00Z"));
+        assert_eq!(after.unwrap().adif, 230);
+        assert_eq!(
+            analyzer.stats(),
+            CacheStats {
+                hits: 0,
+                misses: 2,
+                evictions: 0,
+                entries: 0
+            }
+        );
+    }
+}
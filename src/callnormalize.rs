@@ -0,0 +1,195 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Normalization of callsigns before the analysis.
+//!
+//! Callsigns from hand-typed logs often contain small mistakes like lowercase letters, surrounding whitespace or a slashed zero `Ø`.
+//! In [strict](Normalization::Strict) mode such callsigns are rejected, while the [lenient](Normalization::Lenient) mode fixes them and reports the applied [fixes](Fix).
+
+use crate::call::CallsignError;
+use crate::callparts::find_format_error;
+
+/// Characters that look like a character allowed within callsigns
+const LOOK_ALIKES: [(char, char); 2] = [('Ø', '0'), ('∅', '0')];
+
+/// Mode of the normalization
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Normalization {
+    /// Accept only callsigns that are already in the expected format
+    #[default]
+    Strict,
+    /// Fix common mistakes before checking the format
+    Lenient,
+}
+
+/// Fix applied to a callsign while normalizing it
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum Fix {
+    /// Removed surrounding whitespace
+    Trimmed,
+    /// Converted lowercase letters to uppercase
+    Uppercased,
+    /// Replaced a character by the one it looks like, e.g. `Ø` by `0`
+    LookAlike {
+        /// Replaced character
+        from: char,
+        /// Inserted character
+        to: char,
+    },
+    /// Replaced backslashes by slashes
+    Backslash,
+    /// Collapsed repeated separators like `//` into a single one
+    RepeatedSeparator,
+}
+
+/// Normalized callsign
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Normalized {
+    /// Callsign after the normalization
+    pub call: String,
+    /// Fixes applied in the order of their application, empty if the callsign was not modified
+    pub fixes: Vec<Fix>,
+}
+
+/// Normalize a callsign and check its basic format.
+///
+/// # Arguments
+///
+/// - `call`: Callsign to normalize
+/// - `mode`: Mode of the normalization
+///
+/// # Returns
+///
+/// Normalized callsign or an error if the callsign is of invalid format after the normalization
+pub fn normalize_callsign(call: &str, mode: Normalization) -> Result<Normalized, CallsignError> {
+    let normalized = match mode {
+        Normalization::Strict => Normalized {
+            call: String::from(call),
+            fixes: Vec::new(),
+        },
+        Normalization::Lenient => fix(call),
+    };
+
+    // Check that only allowed characters are present and the callsign does not begin or end with a /
+    // Empty parts like in W1AW//P are not allowed either, as well as callsigns of a single character
+    let position = find_format_error(&normalized.call).or_else(|| {
+        let len = normalized.call.chars().count();
+        (len < 2).then_some(len)
+    });
+    if let Some(position) = position {
+        return Err(CallsignError::BasicFormat { position });
+    }
+
+    Ok(normalized)
+}
+
+/// Fix common mistakes within a callsign.
+///
+/// # Arguments
+///
+/// - `call`: Callsign to fix
+///
+/// # Returns
+///
+/// Fixed callsign
+fn fix(call: &str) -> Normalized {
+    let mut fixes = Vec::new();
+
+    let trimmed = call.trim();
+    if trimmed.len() != call.len() {
+        fixes.push(Fix::Trimmed);
+    }
+
+    let mut fixed = trimmed.to_uppercase();
+    if fixed != trimmed {
+        fixes.push(Fix::Uppercased);
+    }
+
+    for (from, to) in LOOK_ALIKES {
+        if fixed.contains(from) {
+            fixed = fixed.replace(from, &to.to_string());
+            fixes.push(Fix::LookAlike { from, to });
+        }
+    }
+
+    if fixed.contains('\\') {
+        fixed = fixed.replace('\\', "/");
+        fixes.push(Fix::Backslash);
+    }
+
+    if fixed.contains("//") {
+        let mut collapsed = String::with_capacity(fixed.len());
+        for c in fixed.chars() {
+            if !(c == '/' && collapsed.ends_with('/')) {
+                collapsed.push(c);
+            }
+        }
+        fixed = collapsed;
+        fixes.push(Fix::RepeatedSeparator);
+    }
+
+    Normalized { call: fixed, fixes }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize() {
+        assert_eq!(
+            normalize_callsign("W1AW/P", Normalization::Strict),
+            Ok(Normalized {
+                call: String::from("W1AW/P"),
+                fixes: vec![]
+            })
+        );
+        for (call, position) in [
+            (" w1aw", 0),
+            ("W1AW//P", 5),
+            ("SVØABC", 2),
+            ("F\\W1AW", 1),
+            ("W1AW/", 4),
+            ("W", 1),
+        ] {
+            assert_eq!(
+                normalize_callsign(call, Normalization::Strict),
+                Err(CallsignError::BasicFormat { position }),
+                "{}",
+                call
+            );
+        }
+
+        assert_eq!(
+            normalize_callsign(" f\\\\svøabc//p\t", Normalization::Lenient),
+            Ok(Normalized {
+                call: String::from("F/SV0ABC/P"),
+                fixes: vec![
+                    Fix::Trimmed,
+                    Fix::Uppercased,
+                    Fix::LookAlike {
+                        from: 'Ø', to: '0'
+                    },
+                    Fix::Backslash,
+                    Fix::RepeatedSeparator
+                ]
+            })
+        );
+        assert_eq!(
+            normalize_callsign("W1AW", Normalization::Lenient).map(|n| n.fixes),
+            Ok(vec![])
+        );
+        assert_eq!(
+            normalize_callsign("W1AW/", Normalization::Lenient),
+            Err(CallsignError::BasicFormat { position: 4 })
+        );
+        assert_eq!(
+            normalize_callsign("W1 AW", Normalization::Lenient),
+            Err(CallsignError::BasicFormat { position: 2 })
+        );
+    }
+}
@@ -0,0 +1,318 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Structural decomposition of callsigns.
+//!
+//! A callsign like `F/W1ABC/P` consists of the home call `W1ABC`, the location prefix `F` and the appendix `P`.
+//! The home call itself consists of a prefix `W`, the numeral `1` and the suffix `ABC`.
+//!
+//! The result of the [callsign analysis](crate::call::analyze_callsign) contains the decomposition based on the ClubLog data.
+//! Without the data, [parse_callsign] decomposes a callsign solely by its format.
+
+use crate::call::{CallsignError, APPENDIX_SPECIAL};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// Structure of a callsign
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CallsignParts {
+    /// Home call, e.g. `W1ABC` for `F/W1ABC/P`
+    pub homecall: String,
+    /// Effective prefix of the callsign.
+    /// With ClubLog data this is the matched prefix, e.g. `F` for `F/W1ABC/P` or `SV/A` for `SV1ABC/A`, or `None` if a callsign exception applies.
+    /// Without ClubLog data this is the location prefix if present, otherwise the beginning of the home call including the numeral, e.g. `W1` for `W1ABC`.
+    pub prefix: Option<String>,
+    /// Portable or location prefix, e.g. `F` for `F/W1ABC/P` or `CE0Y` for `W1ABC/CE0Y`
+    pub location_prefix: Option<String>,
+    /// Numeral of the home call, e.g. `1` for `W1ABC`
+    pub numeral: Option<String>,
+    /// Suffix of the home call, e.g. `ABC` for `W1ABC` or `None` for `3D2`
+    pub suffix: Option<String>,
+    /// Appendices in the order of the callsign, e.g. `P`, `QRP` or single digits
+    pub appendices: Vec<String>,
+}
+
+impl CallsignParts {
+    /// Assemble the structure from the parts of a callsign split by `/`.
+    ///
+    /// # Arguments
+    ///
+    /// - `parts`: Parts of the callsign
+    /// - `homecall`: Index of the home call
+    /// - `location_prefix`: Index of the location prefix, if any
+    /// - `prefix`: Effective prefix, if known
+    ///
+    /// # Returns
+    ///
+    /// Structure of the callsign
+    pub(crate) fn from_parts(
+        parts: &[&str],
+        homecall: usize,
+        location_prefix: Option<usize>,
+        prefix: Option<&str>,
+    ) -> CallsignParts {
+        let (numeral, suffix) = match split_homecall(parts[homecall]) {
+            Some((_, numeral, suffix)) => (
+                Some(String::from(numeral)),
+                (!suffix.is_empty()).then(|| String::from(suffix)),
+            ),
+            None => (None, None),
+        };
+
+        CallsignParts {
+            homecall: String::from(parts[homecall]),
+            prefix: prefix.map(String::from),
+            location_prefix: location_prefix.map(|idx| String::from(parts[idx])),
+            numeral,
+            suffix,
+            appendices: parts
+                .iter()
+                .enumerate()
+                .filter(|(idx, _)| *idx != homecall && Some(*idx) != location_prefix)
+                .map(|(_, part)| String::from(*part))
+                .collect(),
+        }
+    }
+}
+
+/// Decompose a callsign by its format, without ClubLog data.
+///
+/// The longest part that looks like a complete callsign, i.e. prefix, numeral and suffix, is the home call.
+/// On the same length, the first one wins.
+/// The part in front of the home call is the location prefix.
+/// Without such part, the first part behind the home call that looks like a prefix, e.g. `KL7` or `CE0Y`, is the location prefix.
+/// All other parts are appendices.
+///
+/// Since the decomposition does not know about actual prefixes, it may differ from the one of the [callsign analysis](crate::call::analyze_callsign).
+///
+/// # Arguments
+///
+/// - `call`: Callsign to decompose
+///
+/// # Returns
+///
+/// Structure of the callsign or an error if the callsign is of invalid format
+pub fn parse_callsign(call: &str) -> Result<CallsignParts, CallsignError> {
+    lazy_static! {
+        static ref RE_PREFIX: Regex =
+            Regex::new(r"^(\d?[A-Z]{1,2}\d{0,2}|[A-Z]{1,2}\d[A-Z])$").unwrap();
+    }
+
+    if let Some(position) = find_format_error(call) {
+        return Err(CallsignError::BasicFormat { position });
+    }
+
+    let parts: Vec<&str> = call.split('/').collect();
+
+    // Search for the home call, fall back to the longest part at all
+    let is_homecall =
+        |part: &str| split_homecall(part).is_some_and(|(_, _, suffix)| !suffix.is_empty());
+    let homecall = longest(&parts, is_homecall)
+        .or_else(|| longest(&parts, |_| true))
+        .unwrap_or_default();
+
+    let location_prefix = match homecall {
+        0 => parts
+            .iter()
+            .enumerate()
+            .skip(1)
+            .find(|(_, part)| !APPENDIX_SPECIAL.contains(part) && RE_PREFIX.is_match(part))
+            .map(|(idx, _)| idx),
+        _ => Some(homecall - 1),
+    };
+
+    let prefix = match location_prefix {
+        Some(idx) => Some(String::from(parts[idx])),
+        None => split_homecall(parts[homecall])
+            .map(|(prefix, numeral, _)| format!("{}{}", prefix, numeral)),
+    };
+
+    Ok(CallsignParts {
+        prefix,
+        ..CallsignParts::from_parts(&parts, homecall, location_prefix, None)
+    })
+}
+
+/// Search for the first position that violates the basic format of a callsign.
+///
+/// A callsign consists of uppercase letters and digits, separated by single slashes.
+/// It neither begins nor ends with a slash.
+///
+/// # Arguments
+///
+/// - `call`: Callsign to check
+///
+/// # Returns
+///
+/// Position of the first offending character counted in characters, `None` if the format is valid.
+/// For an empty callsign the position is zero.
+pub(crate) fn find_format_error(call: &str) -> Option<usize> {
+    let chars: Vec<char> = call.chars().collect();
+
+    chars
+        .iter()
+        .position(|c| !(c.is_ascii_uppercase() || c.is_ascii_digit() || *c == '/'))
+        .or_else(|| matches!(chars.first(), None | Some('/')).then_some(0))
+        .or_else(|| {
+            chars
+                .windows(2)
+                .position(|w| w == ['/', '/'])
+                .map(|pos| pos + 1)
+        })
+        .or_else(|| (chars.last() == Some(&'/')).then_some(chars.len() - 1))
+}
+
+/// Search for the longest part matching a condition, the first one wins on the same length.
+///
+/// # Arguments
+///
+/// - `parts`: Parts of the callsign
+/// - `condition`: Condition a part must match
+///
+/// # Returns
+///
+/// Index of the longest matching part, `None` if no part matches
+fn longest<F: Fn(&str) -> bool>(parts: &[&str], condition: F) -> Option<usize> {
+    parts
+        .iter()
+        .enumerate()
+        .filter(|(_, part)| condition(part))
+        .fold(None, |best: Option<(usize, &&str)>, cur| match best {
+            Some(best) if best.1.len() >= cur.1.len() => Some(best),
+            _ => Some(cur),
+        })
+        .map(|(idx, _)| idx)
+}
+
+/// Split a home call into its prefix, numeral and suffix.
+///
+/// # Arguments
+///
+/// - `homecall`: Home call to split
+///
+/// # Returns
+///
+/// Prefix, numeral and suffix, `None` if the home call does not contain a numeral following the prefix
+fn split_homecall(homecall: &str) -> Option<(&str, &str, &str)> {
+    lazy_static! {
+        static ref RE_HOMECALL: Regex = Regex::new(r"^(\d?[A-Z]+)(\d+)([A-Z0-9]*)$").unwrap();
+    }
+
+    RE_HOMECALL.captures(homecall).map(|c| {
+        (
+            c.get(1).unwrap().as_str(),
+            c.get(2).unwrap().as_str(),
+            c.get(3).unwrap().as_str(),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(
+        homecall: &str,
+        prefix: Option<&str>,
+        location_prefix: Option<&str>,
+        numeral: Option<&str>,
+        suffix: Option<&str>,
+        appendices: &[&str],
+    ) -> CallsignParts {
+        CallsignParts {
+            homecall: String::from(homecall),
+            prefix: prefix.map(String::from),
+            location_prefix: location_prefix.map(String::from),
+            numeral: numeral.map(String::from),
+            suffix: suffix.map(String::from),
+            appendices: appendices.iter().map(|a| String::from(*a)).collect(),
+        }
+    }
+
+    #[test]
+    fn parse_without_data() {
+        let calls = [
+            (
+                "W1ABC",
+                parts("W1ABC", Some("W1"), None, Some("1"), Some("ABC"), &[]),
+            ),
+            ("3D2", parts("3D2", Some("3D2"), None, Some("2"), None, &[])),
+            (
+                "F/W1ABC/P",
+                parts(
+                    "W1ABC",
+                    Some("F"),
+                    Some("F"),
+                    Some("1"),
+                    Some("ABC"),
+                    &["P"],
+                ),
+            ),
+            (
+                "W1ABC/CE0Y/QRP",
+                parts(
+                    "W1ABC",
+                    Some("CE0Y"),
+                    Some("CE0Y"),
+                    Some("1"),
+                    Some("ABC"),
+                    &["QRP"],
+                ),
+            ),
+            (
+                "SV0ABC/9",
+                parts("SV0ABC", Some("SV0"), None, Some("0"), Some("ABC"), &["9"]),
+            ),
+            (
+                "LM2T70Y",
+                parts("LM2T70Y", Some("LM2"), None, Some("2"), Some("T70Y"), &[]),
+            ),
+            (
+                "3D2/W1ABC/R",
+                parts(
+                    "W1ABC",
+                    Some("3D2"),
+                    Some("3D2"),
+                    Some("1"),
+                    Some("ABC"),
+                    &["R"],
+                ),
+            ),
+            (
+                "EA8VK/URE",
+                parts("EA8VK", Some("EA8"), None, Some("8"), Some("VK"), &["URE"]),
+            ),
+            (
+                "RW0A",
+                parts("RW0A", Some("RW0"), None, Some("0"), Some("A"), &[]),
+            ),
+            ("DL", parts("DL", None, None, None, None, &[])),
+            (
+                "K1A/PORTABLE",
+                parts("K1A", Some("K1"), None, Some("1"), Some("A"), &["PORTABLE"]),
+            ),
+        ];
+
+        for (call, expected) in calls.iter() {
+            assert_eq!(parse_callsign(call).as_ref(), Ok(expected), "{}", call);
+        }
+
+        for (call, position) in [
+            ("W1AW/", 4),
+            ("/W1AW", 0),
+            ("W1AW//P", 5),
+            ("w1aw", 0),
+            ("W1aw", 2),
+            ("", 0),
+        ] {
+            assert_eq!(
+                parse_callsign(call),
+                Err(CallsignError::BasicFormat { position }),
+                "{}",
+                call
+            );
+        }
+    }
+}
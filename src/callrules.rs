@@ -0,0 +1,839 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Rule engine of the callsign analysis.
+//!
+//! The analysis of a callsign is made of a chain of [rules](AnalysisRule) that work on a shared [Analysis].
+//! The [default chain](RuleChain::default) implements the analysis of [analyze_callsign](crate::call::analyze_callsign) and consists of the following rules:
+//!
+//! 1. [InvalidOperationRule]: Reject callsigns used in an invalid operation
+//! 2. [CallsignExceptionRule]: Take the result from a callsign exception
+//! 3. [ClassificationRule]: Classify each part of the callsign and determine the prefix
+//! 4. [SpecialAppendixRule]: Handle special appendices like `/MM` that indicate no entity
+//! 5. [SingleDigitAppendixRule]: Handle single digit appendices that change the prefix like `/9` for `SV0ABC/9`
+//! 6. [ZoneExceptionRule]: Apply a CQ zone exception
+//!
+//! The chain may be reordered, rules may be removed or own rules may be added, for example to handle known limitations like `LS4AA/F`.
+//! The chain stops as soon as a rule sets the [resulting callsign](Analysis::callsign).
+//! Otherwise the result is built from the [prefix](Analysis::prefix) determined by the rules.
+
+use crate::call::{
+    get_no_entity_appendix, get_prefix, is_different_prefix_by_single_digit_appendix,
+    whitelist_status, AnalyzeOptions, Callsign, CallsignError, Explanation, Part, PartType,
+    PrefixMatch, SingleLetterPolicy, State, APPENDIX_SPECIAL,
+};
+use crate::callnormalize::normalize_callsign;
+use crate::callparts::{parse_callsign, CallsignParts};
+use crate::clublog::{CqZone, Prefix};
+use crate::clublogquery::{is_in_time_window, ClubLogQuery};
+use chrono::{DateTime, Utc};
+use std::borrow::Cow;
+
+/// State of the analysis of a single callsign shared between the rules
+pub struct Analysis<'a> {
+    /// ClubLog data
+    pub clublog: &'a dyn ClubLogQuery,
+    /// Callsign to analyze
+    pub call: &'a str,
+    /// Timestamp to use for the analysis
+    pub timestamp: &'a DateTime<Utc>,
+    /// Options of the analysis
+    pub options: &'a AnalyzeOptions,
+    /// Policy for single letter appendices that applies to the callsign
+    pub single_letter: SingleLetterPolicy,
+    /// Parts of the callsign split by `/`
+    pub parts: Vec<&'a str>,
+    /// Classification of the parts, empty until classified
+    pub parttypes: Vec<PartType>,
+    /// Final state of the classification statemachine
+    pub state: State,
+    /// Prefix the result is based on together with the number of chars removed from the part before it matched
+    pub prefix: Option<(&'a Prefix, usize)>,
+    /// Index of the part that is the homecall
+    pub homecall: usize,
+    /// Index of the part that is the location prefix
+    pub location: Option<usize>,
+    /// CQ zone that replaces the one of the result
+    pub cqzone: Option<CqZone>,
+    /// Resulting callsign, setting it stops the chain
+    pub callsign: Option<Callsign>,
+    /// Trace of the analysis
+    pub explanation: Explanation,
+}
+
+impl<'a> Analysis<'a> {
+    /// Create a new analysis.
+    ///
+    /// # Arguments
+    ///
+    /// - `clublog`: Reference to ClubLog data
+    /// - `call`: Callsign to analyze
+    /// - `timestamp`: Timestamp to use for the analysis
+    /// - `options`: Options of the analysis
+    ///
+    /// # Returns
+    ///
+    /// Analysis
+    pub fn new(
+        clublog: &'a dyn ClubLogQuery,
+        call: &'a str,
+        timestamp: &'a DateTime<Utc>,
+        options: &'a AnalyzeOptions,
+    ) -> Analysis<'a> {
+        // Search for a policy by the entity of the homecall
+        // Since the homecall is not known at this point, it is determined by the format of the callsign
+        let single_letter = match options.entity_single_letter.is_empty() {
+            true => None,
+            false => parse_callsign(call)
+                .ok()
+                .and_then(|parts| clublog.longest_prefix_match(&parts.homecall, timestamp))
+                .and_then(|prefix| options.entity_single_letter.get(&prefix.adif)),
+        };
+
+        Analysis {
+            clublog,
+            call,
+            timestamp,
+            options,
+            single_letter: single_letter.copied().unwrap_or(options.single_letter),
+            parts: call.split('/').collect(),
+            parttypes: Vec::new(),
+            state: State::NoPrefix,
+            prefix: None,
+            homecall: 0,
+            location: None,
+            cqzone: None,
+            callsign: None,
+            explanation: Explanation::default(),
+        }
+    }
+
+    /// Search for the longest prefix matching a part of the callsign.
+    /// Special prefixes like `SV/A` made of the part and a following single char appendix are considered as well.
+    ///
+    /// # Arguments
+    ///
+    /// - `idx`: Index of the part
+    ///
+    /// # Returns
+    ///
+    /// Prefix together with the number of removed chars, `None` if there is no match
+    pub fn find_prefix(&self, idx: usize) -> Option<(&'a Prefix, usize)> {
+        get_prefix(
+            self.clublog,
+            self.parts[idx],
+            self.timestamp,
+            &self.appendices(idx),
+        )
+    }
+
+    /// Get the parts following a part of the callsign that may form a special prefix together with the part.
+    /// Single letter appendices are left out if the [policy](Analysis::single_letter) does not allow special prefixes.
+    ///
+    /// # Arguments
+    ///
+    /// - `idx`: Index of the part
+    ///
+    /// # Returns
+    ///
+    /// Parts following the part
+    pub fn appendices(&self, idx: usize) -> Cow<'_, [&'a str]> {
+        let appendices = &self.parts[idx + 1..];
+        match self.single_letter.allows_special_prefix() {
+            true => Cow::Borrowed(appendices),
+            false => Cow::Owned(
+                appendices
+                    .iter()
+                    .filter(|a| !is_single_letter(a))
+                    .copied()
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Check whether the classification resulted in a single prefix followed by zero or more appendices.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// True if the callsign consists of a single prefix
+    pub fn is_single_prefix(&self) -> bool {
+        matches!(self.state, State::SinglePrefix | State::PrefixComplete(1))
+    }
+
+    /// Get the structure of the callsign based on the current state of the analysis.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Structure of the callsign
+    pub fn structure(&self) -> CallsignParts {
+        CallsignParts::from_parts(
+            &self.parts,
+            self.homecall,
+            self.location,
+            self.prefix.map(|p| p.0.call.as_str()),
+        )
+    }
+}
+
+/// Single rule of the callsign analysis
+pub trait AnalysisRule: Send + Sync {
+    /// Get the name of the rule, used to identify the rule within a [RuleChain].
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Name of the rule
+    fn name(&self) -> &str;
+
+    /// Apply the rule to the analysis.
+    /// Setting the [resulting callsign](Analysis::callsign) stops the chain.
+    ///
+    /// # Arguments
+    ///
+    /// - `analysis`: Analysis to work on
+    ///
+    /// # Returns
+    ///
+    /// Nothing or an error, which stops the chain
+    fn apply(&self, analysis: &mut Analysis) -> Result<(), CallsignError>;
+}
+
+/// Reject callsigns that were used in an invalid operation
+#[derive(Debug, Default, Clone, Copy)]
+pub struct InvalidOperationRule;
+
+impl AnalysisRule for InvalidOperationRule {
+    fn name(&self) -> &str {
+        "invalid_operation"
+    }
+
+    fn apply(&self, analysis: &mut Analysis) -> Result<(), CallsignError> {
+        if analysis
+            .clublog
+            .is_invalid_operation(analysis.call, analysis.timestamp)
+        {
+            let operation = analysis
+                .clublog
+                .invalid_operation_history(analysis.call)
+                .into_iter()
+                .find(|op| is_in_time_window(analysis.timestamp, op.start, op.end));
+            return Err(CallsignError::InvalidOperation {
+                record: operation.map(|op| op.record),
+                start: operation.and_then(|op| op.start),
+                end: operation.and_then(|op| op.end),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Take the result from a callsign exception if the ClubLog data lists one
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CallsignExceptionRule;
+
+impl AnalysisRule for CallsignExceptionRule {
+    fn name(&self) -> &str {
+        "callsign_exception"
+    }
+
+    fn apply(&self, analysis: &mut Analysis) -> Result<(), CallsignError> {
+        if let Some(call_exc) = analysis
+            .clublog
+            .get_callsign_exception(analysis.call, analysis.timestamp)
+        {
+            analysis.explanation.exception = Some(call_exc.record);
+            analysis.callsign = Some(Callsign::from_exception(analysis.call, call_exc));
+        }
+
+        Ok(())
+    }
+}
+
+/// Classify each part of the callsign whether it is a valid prefix or something else.
+/// Based on the classification, check the basic validity of the callsign and determine the prefix, the homecall and the location prefix.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ClassificationRule;
+
+impl AnalysisRule for ClassificationRule {
+    fn name(&self) -> &str {
+        "classification"
+    }
+
+    fn apply(&self, analysis: &mut Analysis) -> Result<(), CallsignError> {
+        // Iterate through all parts of the callsign and check wether the part of the callsigns is a valid prefix or something else
+        analysis.parttypes.clear();
+        analysis.explanation.parts.clear();
+        let mut prefixes = Vec::with_capacity(analysis.parts.len());
+        for (pos, part) in analysis.parts.iter().enumerate() {
+            let prefix = analysis.find_prefix(pos);
+            prefixes.push(prefix);
+            let pt = if prefix.is_some() {
+                // MM and AM may be valid prefixes or special appendices depending on the position within the complete callsign.
+                // For example MM as a prefix evaluates to Scotland, MM as an appendix indicates a maritime mobile activation.
+                // Special appendices are only valid as those if they are right at the beginning of the callsign.
+                // Therefore ignore the first element of the call and check for special appendices beginning from the second element onwards.
+                if pos >= 1 && APPENDIX_SPECIAL.contains(part) {
+                    PartType::Other
+                } else if pos >= 1
+                    && is_single_letter(part)
+                    && !analysis.single_letter.allows_standalone_prefix()
+                {
+                    // Single letter appendices like the F of LS4AA/F may be excluded from being a prefix
+                    PartType::Other
+                } else {
+                    PartType::Prefix
+                }
+            } else {
+                PartType::Other
+            };
+            analysis.parttypes.push(pt);
+            analysis.explanation.parts.push(Part {
+                part: String::from(*part),
+                parttype: pt,
+                prefix: prefix.map(PrefixMatch::new),
+            });
+        }
+
+        // Check for basic validity with a small statemachine.
+        // For example check that the call begins with a prefix, has not too much prefixes, ...
+        let mut state = State::NoPrefix;
+        analysis.explanation.states = vec![state];
+        for (pos, parttype) in analysis.parttypes.iter().enumerate() {
+            let offending = || (String::from(analysis.parts[pos]), pos);
+            match (&state, parttype) {
+                (State::NoPrefix, PartType::Prefix) => state = State::SinglePrefix,
+                (State::NoPrefix, PartType::Other) => {
+                    let (part, index) = offending();
+                    Err(CallsignError::BeginWithoutPrefix { part, index })?
+                }
+                (State::SinglePrefix, PartType::Prefix) => state = State::DoublePrefix,
+                (State::SinglePrefix, PartType::Other) => state = State::PrefixComplete(1),
+                (State::DoublePrefix, PartType::Prefix) => state = State::PrefixComplete(3),
+                (State::DoublePrefix, PartType::Other) => state = State::PrefixComplete(2),
+                (State::PrefixComplete(_), PartType::Prefix) => {
+                    let (part, index) = offending();
+                    Err(CallsignError::TooMuchPrefixes { part, index })?
+                }
+                (State::PrefixComplete(_), PartType::Other) => (),
+            }
+            analysis.explanation.states.push(state);
+        }
+        analysis.state = state;
+
+        // Determine the prefix together with the index of the homecall and the location prefix
+        // The prefixes found while classifying the parts are reused, parts classified as prefixes always have one, otherwise there is an internal error
+        let found = |idx: usize| prefixes.get(idx).copied().flatten();
+        let (prefix, homecall, location) = match (state, found(0), found(1)) {
+            // The callsign consists of a single prefix and zero or more appendices
+            // Example: W for the homecall W1AW
+            (State::SinglePrefix | State::PrefixComplete(1), Some(pref), _) => (pref, 0, None),
+            // The callsign consists of two prefixes and zero or more appendices
+            (
+                State::DoublePrefix | State::PrefixComplete(2),
+                Some(pref_first),
+                Some(pref_second),
+            ) => {
+                // Check if the first prefix may be a valid special prefix like 3D2/R
+                // Example: "3D2ABC/R" contains two valid prefixes at first sight, 3D2 and R but the first and second prefix together form the special prefix 3D2/R
+                if pref_first.0.call.contains('/') {
+                    (pref_first, 0, None)
+                } else {
+                    // Decide which one to use by how many characters were removed from the potential prefix before it matched a prefix from the list.
+                    // The prefix which required less character removals wins.
+                    // This is probably not 100% correct, but seems good enough.
+                    if pref_first.1 <= pref_second.1 {
+                        (pref_first, 1, Some(0))
+                    } else {
+                        (pref_second, 0, Some(1))
+                    }
+                }
+            }
+            // The callsign consists out of three prefixes and zero or more appendices
+            // This is a very special case and only takes account of calls with a special prefix like 3D2/R and therefore callsigns like 3D2/W1ABC/R.
+            // Calls like 3D2ABC/R are already covered, since there are only two potential valid prefixes.
+            // The call 3D2/W1ABC/R contains three potential valid prefixes 3D2, W and R but 3D2/R is the actual prefix (according to my understanding of the special prefix annotation)
+            (State::PrefixComplete(3), Some(pref), _) => {
+                // The third part is the one prefix too much
+                if !pref.0.call.contains('/') {
+                    return Err(CallsignError::TooMuchPrefixes {
+                        part: String::from(analysis.parts[2]),
+                        index: 2,
+                    });
+                }
+                (pref, 1, Some(0))
+            }
+            _ => panic!("Internal error"),
+        };
+
+        analysis.prefix = Some(prefix);
+        analysis.homecall = homecall;
+        analysis.location = location;
+
+        Ok(())
+    }
+}
+
+/// Handle special appendices like `/AM`, `/MM` or `/SAT` that indicate that the callsign does not belong to an entity.
+/// Only applies to callsigns with a single prefix.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SpecialAppendixRule;
+
+impl AnalysisRule for SpecialAppendixRule {
+    fn name(&self) -> &str {
+        "special_appendix"
+    }
+
+    fn apply(&self, analysis: &mut Analysis) -> Result<(), CallsignError> {
+        if !analysis.is_single_prefix() {
+            return Ok(());
+        }
+
+        // Example: W1ABC/AM
+        if let Some(appendix) = get_no_entity_appendix(&analysis.parts[analysis.homecall + 1..])? {
+            analysis.explanation.special_appendix = Some(String::from(appendix));
+            analysis.callsign = Some(Callsign::new_special_entity(
+                analysis.call,
+                analysis.structure(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Handle single digit appendices that change the prefix of the homecall.
+/// Only applies to callsigns with a single prefix.
+///
+/// Example: "SV0ABC/9" where SV is Greece, but SV9 is Crete
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SingleDigitAppendixRule;
+
+impl AnalysisRule for SingleDigitAppendixRule {
+    fn name(&self) -> &str {
+        "single_digit_appendix"
+    }
+
+    fn apply(&self, analysis: &mut Analysis) -> Result<(), CallsignError> {
+        if !analysis.is_single_prefix() {
+            return Ok(());
+        }
+
+        if let Some((digit, pref)) = is_different_prefix_by_single_digit_appendix(
+            analysis.clublog,
+            analysis.parts[analysis.homecall],
+            analysis.timestamp,
+            &analysis.appendices(analysis.homecall),
+        )? {
+            // Only record the appendix if it actually lead to a different prefix
+            if analysis.prefix.map(|p| p.0.record) != Some(pref.0.record) {
+                analysis.explanation.single_digit_appendix = Some(String::from(digit));
+            }
+            analysis.prefix = Some(pref);
+        }
+
+        Ok(())
+    }
+}
+
+/// Replace the CQ zone of the result if the ClubLog data lists a zone exception for the callsign
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ZoneExceptionRule;
+
+impl AnalysisRule for ZoneExceptionRule {
+    fn name(&self) -> &str {
+        "zone_exception"
+    }
+
+    fn apply(&self, analysis: &mut Analysis) -> Result<(), CallsignError> {
+        if let Some(cqz) = analysis
+            .clublog
+            .get_zone_exception(analysis.call, analysis.timestamp)
+        {
+            analysis.cqzone = Some(cqz);
+            analysis.explanation.zone_exception = Some(cqz);
+        }
+
+        Ok(())
+    }
+}
+
+/// Ordered chain of rules to analyze callsigns
+pub struct RuleChain {
+    /// Rules in the order of their application
+    rules: Vec<Box<dyn AnalysisRule>>,
+}
+
+impl Default for RuleChain {
+    /// Create the default chain that implements the analysis of [analyze_callsign](crate::call::analyze_callsign).
+    fn default() -> Self {
+        RuleChain::new()
+            .with_rule(InvalidOperationRule)
+            .with_rule(CallsignExceptionRule)
+            .with_rule(ClassificationRule)
+            .with_rule(SpecialAppendixRule)
+            .with_rule(SingleDigitAppendixRule)
+            .with_rule(ZoneExceptionRule)
+    }
+}
+
+impl RuleChain {
+    /// Create a new chain without any rule.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Empty chain
+    pub fn new() -> Self {
+        RuleChain { rules: Vec::new() }
+    }
+
+    /// Append a rule to the end of the chain.
+    ///
+    /// # Arguments
+    ///
+    /// - `rule`: Rule to append
+    ///
+    /// # Returns
+    ///
+    /// Modified chain
+    pub fn with_rule<R: AnalysisRule + 'static>(mut self, rule: R) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Insert a rule at a position within the chain.
+    ///
+    /// # Arguments
+    ///
+    /// - `index`: Position of the rule, see [position](RuleChain::position)
+    /// - `rule`: Rule to insert
+    ///
+    /// # Returns
+    ///
+    /// (None)
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is greater than the number of rules.
+    pub fn insert<R: AnalysisRule + 'static>(&mut self, index: usize, rule: R) {
+        self.rules.insert(index, Box::new(rule));
+    }
+
+    /// Remove a rule from the chain.
+    ///
+    /// # Arguments
+    ///
+    /// - `name`: Name of the rule
+    ///
+    /// # Returns
+    ///
+    /// Removed rule, `None` if the chain does not contain a rule with the name
+    pub fn remove(&mut self, name: &str) -> Option<Box<dyn AnalysisRule>> {
+        self.position(name).map(|idx| self.rules.remove(idx))
+    }
+
+    /// Move a rule to a different position within the chain.
+    ///
+    /// # Arguments
+    ///
+    /// - `name`: Name of the rule
+    /// - `index`: New position of the rule
+    ///
+    /// # Returns
+    ///
+    /// True if the rule was moved, false if the chain does not contain a rule with the name
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is not smaller than the number of rules.
+    pub fn reorder(&mut self, name: &str, index: usize) -> bool {
+        match self.remove(name) {
+            Some(rule) => {
+                self.rules.insert(index, rule);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get the position of a rule within the chain.
+    ///
+    /// # Arguments
+    ///
+    /// - `name`: Name of the rule
+    ///
+    /// # Returns
+    ///
+    /// Position of the rule, `None` if the chain does not contain a rule with the name
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.rules.iter().position(|r| r.name() == name)
+    }
+
+    /// Get the names of all rules in the order of their application.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Names of the rules
+    pub fn names(&self) -> Vec<&str> {
+        self.rules.iter().map(|r| r.name()).collect()
+    }
+
+    /// Analyze a callsign by applying the rules of the chain.
+    ///
+    /// # Arguments
+    ///
+    /// - `clublog`: Reference to ClubLog data
+    /// - `call`: Callsign to analyze
+    /// - `timestamp`: Timestamp to use for the analysis
+    /// - `options`: Options of the analysis
+    ///
+    /// # Returns
+    ///
+    /// Returns further information about the callsign or an error.
+    pub fn analyze(
+        &self,
+        clublog: &dyn ClubLogQuery,
+        call: &str,
+        timestamp: &DateTime<Utc>,
+        options: &AnalyzeOptions,
+    ) -> Result<Callsign, CallsignError> {
+        self.analyze_explained(clublog, call, timestamp, options).0
+    }
+
+    /// Analyze a callsign by applying the rules of the chain and trace the decisions made on the way.
+    ///
+    /// # Arguments
+    ///
+    /// - `clublog`: Reference to ClubLog data
+    /// - `call`: Callsign to analyze
+    /// - `timestamp`: Timestamp to use for the analysis
+    /// - `options`: Options of the analysis
+    ///
+    /// # Returns
+    ///
+    /// Returns further information about the callsign or an error together with the trace of the analysis.
+    pub fn analyze_explained(
+        &self,
+        clublog: &dyn ClubLogQuery,
+        call: &str,
+        timestamp: &DateTime<Utc>,
+        options: &AnalyzeOptions,
+    ) -> (Result<Callsign, CallsignError>, Explanation) {
+        let normalized = match normalize_callsign(call, options.normalization) {
+            Ok(normalized) => normalized,
+            Err(e) => return (Err(e), Explanation::default()),
+        };
+
+        let mut analysis = Analysis::new(clublog, &normalized.call, timestamp, options);
+        analysis.explanation.fixes = normalized.fixes;
+        let result = self.run(&mut analysis);
+
+        (result, analysis.explanation)
+    }
+
+    /// Apply the rules of the chain and build the result.
+    ///
+    /// # Arguments
+    ///
+    /// - `analysis`: Analysis to work on
+    ///
+    /// # Returns
+    ///
+    /// Returns further information about the callsign or an error.
+    fn run(&self, analysis: &mut Analysis) -> Result<Callsign, CallsignError> {
+        for rule in self.rules.iter() {
+            rule.apply(analysis)?;
+            if analysis.callsign.is_some() {
+                break;
+            }
+        }
+
+        // Without a result of a rule, build the result from the prefix
+        let mut callsign = match analysis.callsign.take() {
+            Some(callsign) => callsign,
+            None => {
+                let prefix = analysis
+                    .prefix
+                    .ok_or_else(|| CallsignError::BeginWithoutPrefix {
+                        part: String::from(analysis.parts[0]),
+                        index: 0,
+                    })?;
+                analysis.explanation.prefix = Some(PrefixMatch::new(prefix));
+                Callsign::from_prefix(analysis.call, prefix.0, analysis.structure())
+            }
+        };
+
+        if let Some(cqz) = analysis.cqzone {
+            callsign.cqzone = Some(cqz);
+        }
+        callsign.whitelist = whitelist_status(analysis.clublog, &callsign, analysis.timestamp);
+
+        Ok(callsign)
+    }
+}
+
+/// Check whether a part of a callsign is a single letter.
+///
+/// # Arguments
+///
+/// - `part`: Part of the callsign
+///
+/// # Returns
+///
+/// True if the part is a single letter
+fn is_single_letter(part: &str) -> bool {
+    part.len() == 1 && part.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::call::analyze_callsign;
+    use crate::clublog::ClubLog;
+    use crate::clublogmap::ClubLogMap;
+
+    /// Interpret single letter appendices behind Argentinian callsigns as area designators
+    struct ArgentinaRule;
+
+    impl AnalysisRule for ArgentinaRule {
+        fn name(&self) -> &str {
+            "argentina"
+        }
+
+        fn apply(&self, analysis: &mut Analysis) -> Result<(), CallsignError> {
+            if let Some(location) = analysis.location {
+                let home = analysis.find_prefix(analysis.homecall);
+                if analysis.parts[location].len() == 1 && home.is_some_and(|p| p.0.adif == 100) {
+                    analysis.prefix = home;
+                    analysis.location = None;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn custom_chain() {
+        let argentina = r#"<prefix record="15"><call>LU</call><entity>ARGENTINA</entity><adif>100</adif></prefix>
+<prefix record="16"><call>F</call><entity>FRANCE</entity><adif>227</adif></prefix>
+</prefixes>"#;
+        let exception = r#"<exception record="2"><call>4U1UN</call><entity>UNITED NATIONS HQ</entity><adif>289</adif></exception>
+</exceptions>"#;
+        let clublog = ClubLogMap::from(
+            ClubLog::parse(
+                &include_str!("../tests/data/cty_sample.xml")
+                    .replace("</prefixes>", argentina)
+                    .replace("</exceptions>", exception),
+            )
+            .unwrap(),
+        );
+        let timestamp = DateTime::parse_from_rfc3339("2020-01-01T00:00:00Z")
+            .unwrap()
+            .into();
+
+        let options = AnalyzeOptions::default();
+
+        // Default chain equals the regular analysis
+        let chain = RuleChain::default();
+        assert_eq!(
+            chain.names(),
+            vec![
+                "invalid_operation",
+                "callsign_exception",
+                "classification",
+                "special_appendix",
+                "single_digit_appendix",
+                "zone_exception"
+            ]
+        );
+        for call in ["LU1ABC/F", "VE1ABC", "DL0XX", "DL1ABC/MM", "ABC", "4U1UN"] {
+            assert_eq!(
+                chain.analyze(&clublog, call, &timestamp, &options),
+                analyze_callsign(&clublog, call, &timestamp)
+            );
+        }
+        assert_eq!(
+            chain
+                .analyze(&clublog, "LU1ABC/F", &timestamp, &options)
+                .unwrap()
+                .adif,
+            227
+        );
+
+        // Extend the chain
+        let mut chain = RuleChain::default();
+        chain.insert(chain.position("classification").unwrap() + 1, ArgentinaRule);
+        let res = chain
+            .analyze(&clublog, "LU1ABC/F", &timestamp, &options)
+            .unwrap();
+        assert_eq!(res.adif, 100);
+        assert_eq!(res.parts.appendices, vec![String::from("F")]);
+
+        // Disable rules
+        assert!(chain.remove("zone_exception").is_some());
+        assert!(chain.remove("zone_exception").is_none());
+        assert_eq!(
+            chain
+                .analyze(&clublog, "VE1ABC", &timestamp, &options)
+                .unwrap()
+                .cqzone,
+            Some(5)
+        );
+        chain.remove("invalid_operation");
+        assert!(chain
+            .analyze(&clublog, "DL0XX", &timestamp, &options)
+            .is_ok());
+
+        // Reorder rules, the classification fails before the callsign exception applies
+        let mut chain = RuleChain::default();
+        assert_eq!(
+            chain
+                .analyze(&clublog, "4U1UN", &timestamp, &options)
+                .unwrap()
+                .adif,
+            289
+        );
+        assert!(chain.reorder("callsign_exception", 2));
+        assert!(!chain.reorder("unknown", 0));
+        assert_eq!(
+            chain.names()[..3],
+            ["invalid_operation", "classification", "callsign_exception"]
+        );
+        assert_eq!(
+            chain.analyze(&clublog, "4U1UN", &timestamp, &options),
+            Err(CallsignError::BeginWithoutPrefix {
+                part: String::from("4U1UN"),
+                index: 0
+            })
+        );
+
+        // Empty chain does not find a prefix
+        assert_eq!(
+            RuleChain::new().analyze(&clublog, "DL1ABC", &timestamp, &options),
+            Err(CallsignError::BeginWithoutPrefix {
+                part: String::from("DL1ABC"),
+                index: 0
+            })
+        );
+    }
+}
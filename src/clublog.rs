@@ -2,15 +2,31 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-//! Implementation of a parser based on deserialization for the ClubLog XML based entity and callsign information.
+//! Implementation of a parser for the ClubLog XML based entity and callsign information.
 //! Next to that, the module provides a few basic methods to query information from the parsed data.
+//! The parsed data may also be serialized back into the XML format, for example after filtering or modifying it.
+//! Since the record types use the names of the XML file, other serde formats like JSON use these names as well, e.g. `@record` for the record identifier.
+//! The names are stable and follow the XML schema of ClubLog.
+//! Timestamps are written as RFC 3339 strings with offset, the same format the results of the callsign analysis use.
 //!
 //! The example `clublog.rs` shows the basic usage of this module.
 
-use crate::clublogquery::{is_in_time_window, ClubLogQuery};
+pub use crate::clublogdiff::diff;
+use crate::clublogparser;
+use crate::clublogquery::{
+    chronological, entity_name_matches, is_in_time_window, ClubLogQuery, QueryIter,
+};
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Deserializer};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+#[cfg(feature = "gzip")]
+use std::io::{Chain, Cursor, Read};
+use std::path::Path;
 use std::vec::Vec;
+use thiserror::Error;
 
 /// ADIF DXCC identifier
 pub type Adif = u16;
@@ -33,12 +49,97 @@ pub const CALLSIGN_EXCEPTION_AERONAUTICAL_MOBILE: &str = "AERONAUTICAL MOBILE";
 /// Special value for the entity of a callsign exception that is satellite, internet or repeater only
 pub const CALLSIGN_EXCEPTION_SATELLITE: &str = "SATELLITE, INTERNET OR REPEATER";
 
+/// XML namespace of the ClubLog data file
+pub const CLUBLOG_XML_NAMESPACE: &str = "https://clublog.org/cty/v1.2";
+
 /// Special ADIF identifier representing an unknown entity
 pub const ADIF_ID_NO_DXCC: Adif = 0;
 
-/// Errors
-#[derive(Debug)]
-pub struct Error;
+/// Location within the XML document where an error occurred
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    /// Path of the element, like `clublog/prefixes/prefix/adif`
+    pub path: String,
+    /// Identifier of the surrounding record, if any
+    pub record: Option<RecordId>,
+    /// Byte offset within the document
+    pub offset: usize,
+    /// Line within the document, beginning with 1
+    pub line: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {} (byte {}), element '{}'",
+            self.line, self.offset, self.path
+        )?;
+        if let Some(record) = self.record {
+            write!(f, ", record {}", record)?;
+        }
+        Ok(())
+    }
+}
+
+/// Possible errors while parsing the ClubLog XML data
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Reading the data failed
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Data is not well-formed XML
+    #[error("XML syntax error at {location}: {source}")]
+    Xml {
+        /// Location of the error
+        location: Location,
+        /// Error reported by the XML reader
+        source: quick_xml::Error,
+    },
+
+    /// A required element or attribute is missing
+    #[error("Missing element '{element}' at {location}")]
+    MissingElement {
+        /// Name of the missing element, attributes are prefixed with `@`
+        element: String,
+        /// Location of the parent element
+        location: Location,
+    },
+
+    /// Value is not a valid RFC3339 timestamp
+    #[error("Invalid timestamp '{value}' at {location}: {source}")]
+    InvalidTimestamp {
+        /// Raw value
+        value: String,
+        /// Location of the value
+        location: Location,
+        /// Error reported by the timestamp parser
+        source: chrono::ParseError,
+    },
+
+    /// Value is not a valid number
+    #[error("Invalid number '{value}' at {location}")]
+    InvalidNumber {
+        /// Raw value
+        value: String,
+        /// Location of the value
+        location: Location,
+    },
+
+    /// Value is not a valid boolean
+    #[error("Invalid boolean '{value}' at {location}")]
+    InvalidBoolean {
+        /// Raw value
+        value: String,
+        /// Location of the value
+        location: Location,
+    },
+
+    /// Serializing the data into XML failed
+    #[error("Serialization failed: {0}")]
+    Serialize(#[from] quick_xml::DeError),
+}
 
 impl ClubLogQuery for ClubLog {
     fn get_entity(&self, adif: Adif, timestamp: &DateTime<Utc>) -> Option<&Entity> {
@@ -78,6 +179,94 @@ impl ClubLogQuery for ClubLog {
             .iter()
             .any(|o| o.call == callsign && is_in_time_window(timestamp, o.start, o.end))
     }
+    fn iter_entities(&self) -> QueryIter<'_, Entity> {
+        Box::new(self.entities.list.iter())
+    }
+    fn iter_prefixes(&self) -> QueryIter<'_, Prefix> {
+        Box::new(self.prefixes.list.iter())
+    }
+    fn iter_callsign_exceptions(&self) -> QueryIter<'_, CallsignException> {
+        Box::new(self.exceptions.list.iter())
+    }
+    fn iter_zone_exceptions(&self) -> QueryIter<'_, ZoneException> {
+        Box::new(self.zone_exceptions.list.iter())
+    }
+    fn iter_invalid_operations(&self) -> QueryIter<'_, InvalidOperation> {
+        Box::new(self.invalid_operations.list.iter())
+    }
+    fn supports_history(&self) -> bool {
+        true
+    }
+    fn get_entity_by_prefix(&self, prefix: &str, timestamp: &DateTime<Utc>) -> Option<&Entity> {
+        self.entities
+            .list
+            .iter()
+            .find(|e| e.prefix == prefix && is_in_time_window(timestamp, e.start, e.end))
+    }
+    fn entities(&self, timestamp: &DateTime<Utc>) -> QueryIter<'_, Entity> {
+        let timestamp = *timestamp;
+        Box::new(
+            self.entities
+                .list
+                .iter()
+                .filter(move |e| is_in_time_window(&timestamp, e.start, e.end)),
+        )
+    }
+    fn search_entities<'a>(&'a self, name: &str) -> QueryIter<'a, Entity> {
+        let name = name.to_lowercase();
+        Box::new(
+            self.entities
+                .list
+                .iter()
+                .filter(move |e| entity_name_matches(e, &name)),
+        )
+    }
+    fn prefixes_by_adif(&self, adif: Adif) -> QueryIter<'_, Prefix> {
+        Box::new(self.prefixes.list.iter().filter(move |p| p.adif == adif))
+    }
+    fn exceptions_by_adif(&self, adif: Adif) -> QueryIter<'_, CallsignException> {
+        Box::new(self.exceptions.list.iter().filter(move |e| e.adif == adif))
+    }
+    fn prefix_history(&self, prefix: &str) -> Vec<&Prefix> {
+        chronological(
+            self.prefixes
+                .list
+                .iter()
+                .filter(|p| p.call == prefix)
+                .collect(),
+            |p| (p.start, p.record),
+        )
+    }
+    fn callsign_exception_history(&self, callsign: &str) -> Vec<&CallsignException> {
+        chronological(
+            self.exceptions
+                .list
+                .iter()
+                .filter(|e| e.call == callsign)
+                .collect(),
+            |e| (e.start, e.record),
+        )
+    }
+    fn zone_exception_history(&self, callsign: &str) -> Vec<&ZoneException> {
+        chronological(
+            self.zone_exceptions
+                .list
+                .iter()
+                .filter(|z| z.call == callsign)
+                .collect(),
+            |z| (z.start, z.record),
+        )
+    }
+    fn invalid_operation_history(&self, callsign: &str) -> Vec<&InvalidOperation> {
+        chronological(
+            self.invalid_operations
+                .list
+                .iter()
+                .filter(|o| o.call == callsign)
+                .collect(),
+            |o| (o.start, o.record),
+        )
+    }
 }
 
 impl ClubLog {
@@ -89,9 +278,167 @@ impl ClubLog {
     ///
     /// # Returns
     ///
-    /// Parsed ClubLog data or an error
+    /// Parsed ClubLog data or an error describing the location of the failure
     pub fn parse(content: &str) -> Result<Self, Error> {
-        quick_xml::de::from_str(content).map_err(|_| Error)
+        Self::from_reader(content.as_bytes())
+    }
+
+    /// Serialize the ClubLog data into the XML format of the data file.
+    /// The result may be parsed again with [parse](ClubLog::parse).
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// XML formatted content of the data file or an error
+    pub fn to_xml(&self) -> Result<String, Error> {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+
+        let mut serializer = quick_xml::se::Serializer::new(&mut xml);
+        serializer.indent(' ', 2);
+        self.serialize(serializer)?;
+
+        Ok(xml)
+    }
+
+    /// Parse XML formatted ClubLog data while streaming it from a reader.
+    /// The data is processed incrementally without reading the complete content into memory first.
+    ///
+    /// With the feature `gzip` enabled, gzip compressed data like the `cty.xml.gz` offered by ClubLog is detected and decompressed on the fly.
+    ///
+    /// # Arguments
+    ///
+    /// - `reader`: Reader providing the content of the data file, like a file or a decompressor
+    ///
+    /// # Returns
+    ///
+    /// Parsed ClubLog data or an error describing the location of the failure
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, Error> {
+        #[cfg(feature = "gzip")]
+        let reader = match peek_gzip(reader)? {
+            (true, reader) => {
+                return clublogparser::parse(BufReader::new(flate2::bufread::MultiGzDecoder::new(
+                    reader,
+                )))
+            }
+            (false, reader) => reader,
+        };
+
+        clublogparser::parse(reader)
+    }
+
+    /// Read only the timestamp of XML formatted ClubLog data from a reader.
+    /// Reading stops at the root element, so this is much cheaper than parsing the complete data.
+    ///
+    /// With the feature `gzip` enabled, gzip compressed data is detected and decompressed on the fly.
+    ///
+    /// # Arguments
+    ///
+    /// - `reader`: Reader providing the content of the data file
+    ///
+    /// # Returns
+    ///
+    /// Timestamp of the ClubLog data or an error describing the location of the failure, also if the timestamp is missing
+    pub fn date_from_reader<R: BufRead>(reader: R) -> Result<DateTime<Utc>, Error> {
+        #[cfg(feature = "gzip")]
+        let reader = match peek_gzip(reader)? {
+            (true, reader) => {
+                return clublogparser::parse_date(BufReader::new(
+                    flate2::bufread::MultiGzDecoder::new(reader),
+                ))
+            }
+            (false, reader) => reader,
+        };
+
+        clublogparser::parse_date(reader)
+    }
+
+    /// Parse the ClubLog data file at the given path.
+    ///
+    /// # Arguments
+    ///
+    /// - `path`: Path of the data file
+    ///
+    /// # Returns
+    ///
+    /// Parsed ClubLog data or an error describing the location of the failure
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+}
+
+/// Reader providing the bytes peeked at the beginning followed by the remaining data
+#[cfg(feature = "gzip")]
+type Peeked<R> = Chain<Cursor<Vec<u8>>, R>;
+
+/// Check whether the data of a reader is gzip compressed by looking at the magic bytes.
+/// The reader is read until both magic bytes are available or the end of the data is reached, even if it provides less bytes at once.
+///
+/// # Arguments
+///
+/// - `reader`: Reader to check
+///
+/// # Returns
+///
+/// True if the data is gzip compressed, false if not, together with a reader providing the complete data again, or an error
+#[cfg(feature = "gzip")]
+fn peek_gzip<R: BufRead>(mut reader: R) -> Result<(bool, Peeked<R>), Error> {
+    const MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+    let mut head = Vec::with_capacity(MAGIC.len());
+    while head.len() < MAGIC.len() {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+        let len = buf.len().min(MAGIC.len() - head.len());
+        head.extend_from_slice(&buf[..len]);
+        reader.consume(len);
+    }
+
+    Ok((head == MAGIC, Cursor::new(head).chain(reader)))
+}
+
+/// Custom XML serializer for a timestamp
+///
+/// # Arguments
+///
+/// - `timestamp`: Timestamp to serialize
+/// - `serializer`: Serializer
+///
+/// # Returns
+///
+/// Serialized timestamp in RFC3339 format or an error
+fn serialize_datetime<S>(timestamp: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&timestamp.to_rfc3339())
+}
+
+/// Custom serializer for an optional timestamp.
+/// Used by the results of the callsign analysis as well, so all timestamps share the same format.
+///
+/// # Arguments
+///
+/// - `timestamp`: Optional timestamp to serialize
+/// - `serializer`: Serializer
+///
+/// # Returns
+///
+/// Serialized timestamp in RFC3339 format or an error
+pub(crate) fn serialize_datetime_opt<S>(
+    timestamp: &Option<DateTime<Utc>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match timestamp {
+        Some(timestamp) => serialize_datetime(timestamp, serializer),
+        None => serializer.serialize_none(),
     }
 }
 
@@ -137,7 +484,7 @@ where
 }
 
 /// Representation of the club logs callsign lookup data
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, PartialEq, Clone)]
 #[serde(rename = "clublog")]
 pub struct ClubLog {
     /// Timestamp of data
@@ -157,8 +504,25 @@ pub struct ClubLog {
     pub zone_exceptions: ZoneExceptions,
 }
 
+impl Serialize for ClubLog {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("clublog", 7)?;
+        state.serialize_field("@date", &self.date.to_rfc3339())?;
+        state.serialize_field("@xmlns", CLUBLOG_XML_NAMESPACE)?;
+        state.serialize_field("entities", &self.entities)?;
+        state.serialize_field("exceptions", &self.exceptions)?;
+        state.serialize_field("prefixes", &self.prefixes)?;
+        state.serialize_field("invalid_operations", &self.invalid_operations)?;
+        state.serialize_field("zone_exceptions", &self.zone_exceptions)?;
+        state.end()
+    }
+}
+
 /// List of entities / DXCCs
-#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
 pub struct Entities {
     #[serde(rename = "entity")]
     pub list: Vec<Entity>,
@@ -179,7 +543,7 @@ pub struct Entities {
 /// The list of approved callsigns is part of the [callsign exception](CallsignException) list.
 /// May also have a look at the timestamps [whitelist_start](Entity::whitelist_start) and [whitelist_end](Entity::whitelist_end) to check whether a whitelist check is required or not.
 /// Note, that the whitelist timstamps are not necessarily present if a entity is whitelisted.
-#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
 pub struct Entity {
     /// ADIF identifier
     pub adif: Adif,
@@ -190,35 +554,48 @@ pub struct Entity {
     /// Entity deleted/invalid
     pub deleted: bool,
     /// CQ zone
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub cqz: Option<CqZone>,
     /// Continent
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub cont: Option<String>,
     /// Longitude
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub long: Option<f32>,
     /// Latitude
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub lat: Option<f32>,
     /// Start timestamp of validity
     #[serde(default)]
     #[serde(deserialize_with = "parse_datetime_opt")]
+    #[serde(serialize_with = "serialize_datetime_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub start: Option<DateTime<Utc>>,
     /// End timestamp of validity
     #[serde(default)]
     #[serde(deserialize_with = "parse_datetime_opt")]
+    #[serde(serialize_with = "serialize_datetime_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub end: Option<DateTime<Utc>>,
     /// True if only whitelisted of callsigns are valid for this entity
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub whitelist: Option<bool>,
     /// Timestamp after which the whitelist shall be used
     #[serde(default)]
     #[serde(deserialize_with = "parse_datetime_opt")]
+    #[serde(serialize_with = "serialize_datetime_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub whitelist_start: Option<DateTime<Utc>>,
     /// Timestamp after which the whitelist shall not be used anymore
     #[serde(default)]
     #[serde(deserialize_with = "parse_datetime_opt")]
+    #[serde(serialize_with = "serialize_datetime_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub whitelist_end: Option<DateTime<Utc>>,
 }
 
 /// List of callsign exceptions
-#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
 #[serde(rename = "Exceptions")]
 pub struct CallsignExceptions {
     #[serde(rename = "exception")]
@@ -244,7 +621,7 @@ pub struct CallsignExceptions {
 /// There are historical reasons, why the same information is part of two lists.
 ///
 /// Note: Valid callsigns for a [whitelisted entity](Entity::whitelist) are also part of the callsign exception list.
-#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
 #[serde(rename = "Exception")]
 pub struct CallsignException {
     /// Identifier
@@ -257,25 +634,33 @@ pub struct CallsignException {
     /// ADIF identifier
     pub adif: Adif,
     /// CQ zone
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub cqz: Option<CqZone>,
     /// Continent
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub cont: Option<String>,
     /// Longitude
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub long: Option<f32>,
     /// Latitude
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub lat: Option<f32>,
     /// Start timestamp of validity
     #[serde(default)]
     #[serde(deserialize_with = "parse_datetime_opt")]
+    #[serde(serialize_with = "serialize_datetime_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub start: Option<DateTime<Utc>>,
     /// End timestamp of validity
     #[serde(default)]
     #[serde(deserialize_with = "parse_datetime_opt")]
+    #[serde(serialize_with = "serialize_datetime_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub end: Option<DateTime<Utc>>,
 }
 
 /// List of callsign prefixes
-#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
 pub struct Prefixes {
     #[serde(rename = "prefix")]
     pub list: Vec<Prefix>,
@@ -289,7 +674,7 @@ pub struct Prefixes {
 /// While searching for a matching prefix make sure to also validate against the optional [start](Prefix::start) and [end](Prefix::end) timestamps.
 ///
 /// Note: While searching for a prefix, next to obvious prefixes like `DL`, there are also special ones listed like `SV/A`.
-#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
 pub struct Prefix {
     /// Identifier
     #[serde(rename = "@record")]
@@ -301,25 +686,33 @@ pub struct Prefix {
     /// ADIF identifier
     pub adif: Adif,
     /// CQ zone
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub cqz: Option<CqZone>,
     /// Continent
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub cont: Option<String>,
     /// Longitude
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub long: Option<f32>,
     /// Latitude
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub lat: Option<f32>,
     /// Start timestamp of validity
     #[serde(default)]
     #[serde(deserialize_with = "parse_datetime_opt")]
+    #[serde(serialize_with = "serialize_datetime_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub start: Option<DateTime<Utc>>,
     /// End timestamp of validity
     #[serde(default)]
     #[serde(deserialize_with = "parse_datetime_opt")]
+    #[serde(serialize_with = "serialize_datetime_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub end: Option<DateTime<Utc>>,
 }
 
 /// List of invalid operations
-#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
 pub struct InvalidOperations {
     #[serde(rename = "invalid")]
     pub list: Vec<InvalidOperation>,
@@ -332,7 +725,7 @@ pub struct InvalidOperations {
 /// Furthermore, check the validity against the optional [start](InvalidOperation::start) and [end](InvalidOperation::end) timestamps.
 ///
 /// Note: this information is for historical reasons also part of the [callsign exceptions](CallsignException).
-#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
 #[serde(rename = "Invalid")]
 pub struct InvalidOperation {
     /// Identifier
@@ -343,15 +736,19 @@ pub struct InvalidOperation {
     /// Start timestamp of operation
     #[serde(default)]
     #[serde(deserialize_with = "parse_datetime_opt")]
+    #[serde(serialize_with = "serialize_datetime_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub start: Option<DateTime<Utc>>,
     /// End timestamp of operation
     #[serde(default)]
     #[serde(deserialize_with = "parse_datetime_opt")]
+    #[serde(serialize_with = "serialize_datetime_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub end: Option<DateTime<Utc>>,
 }
 
 /// List of CQ zone exceptions
-#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
 pub struct ZoneExceptions {
     #[serde(rename = "zone_exception")]
     pub list: Vec<ZoneException>,
@@ -362,7 +759,7 @@ pub struct ZoneExceptions {
 /// An entry represents a callsign, where the CQ zone of the entity is different.
 /// When searching for a matching entry the [callsign](ZoneException::call) must match exactly including prefix, suffix and appendix.
 /// Furthermore, check the validity against the optional [start](ZoneException::start) and [end](ZoneException::end) timestamps.
-#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
 pub struct ZoneException {
     /// Identifier
     #[serde(rename = "@record")]
@@ -374,10 +771,14 @@ pub struct ZoneException {
     /// Start timestamp of exception
     #[serde(default)]
     #[serde(deserialize_with = "parse_datetime_opt")]
+    #[serde(serialize_with = "serialize_datetime_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub start: Option<DateTime<Utc>>,
     /// End timestamp of exception
     #[serde(default)]
     #[serde(deserialize_with = "parse_datetime_opt")]
+    #[serde(serialize_with = "serialize_datetime_opt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub end: Option<DateTime<Utc>>,
 }
 
@@ -390,10 +791,169 @@ mod tests {
         let clublog =
             ClubLog::parse(&std::fs::read_to_string("data/clublog/cty.xml").unwrap()).unwrap();
 
-        assert!(clublog.entities.list.len() > 0);
-        assert!(clublog.exceptions.list.len() > 0);
-        assert!(clublog.prefixes.list.len() > 0);
-        assert!(clublog.invalid_operations.list.len() > 0);
-        assert!(clublog.zone_exceptions.list.len() > 0);
+        assert!(!clublog.entities.list.is_empty());
+        assert!(!clublog.exceptions.list.is_empty());
+        assert!(!clublog.prefixes.list.is_empty());
+        assert!(!clublog.invalid_operations.list.is_empty());
+        assert!(!clublog.zone_exceptions.list.is_empty());
+    }
+
+    const SAMPLE: &str = include_str!("../tests/data/cty_sample.xml");
+
+    #[test]
+    fn parse_sample() {
+        let clublog = ClubLog::parse(SAMPLE).unwrap();
+
+        assert_eq!(clublog.entities.list.len(), 2);
+        assert_eq!(clublog.exceptions.list[0].record, 1);
+        assert_eq!(clublog.prefixes.list[1].call, "VE");
+        assert_eq!(clublog.invalid_operations.list[0].call, "DL0XX");
+        assert_eq!(clublog.zone_exceptions.list[0].zone, 2);
+        assert_eq!(clublog.zone_exceptions.list[0].end, None);
+    }
+
+    #[test]
+    fn parse_serde() {
+        // The serde deserialization of the records agrees with the streaming parser
+        let clublog: ClubLog = quick_xml::de::from_str(SAMPLE).unwrap();
+
+        assert_eq!(clublog, ClubLog::parse(SAMPLE).unwrap());
+    }
+
+    #[test]
+    fn parse_reader() {
+        let clublog =
+            ClubLog::from_reader(std::io::BufReader::with_capacity(16, SAMPLE.as_bytes())).unwrap();
+
+        assert_eq!(clublog.entities.list.len(), 2);
+        assert_eq!(clublog.prefixes.list.len(), 2);
+    }
+
+    #[test]
+    fn parse_date() {
+        let date = ClubLog::date_from_reader(SAMPLE.as_bytes()).unwrap();
+        assert_eq!(date, ClubLog::parse(SAMPLE).unwrap().date);
+
+        // Only the root element is read, the lists are neither required nor checked
+        let date = ClubLog::date_from_reader(
+            "<clublog date=\"2023-11-25T20:31:27+00:00\"><entities><invalid".as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(date.to_rfc3339(), "2023-11-25T20:31:27+00:00");
+        assert!(matches!(
+            ClubLog::date_from_reader("<cty/>".as_bytes()),
+            Err(Error::MissingElement { .. })
+        ));
+        assert!(matches!(
+            ClubLog::date_from_reader("<clublog>".as_bytes()),
+            Err(Error::MissingElement { element, .. }) if element == "@date"
+        ));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn parse_gzip() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(SAMPLE.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let clublog = ClubLog::from_reader(compressed.as_slice()).unwrap();
+        assert_eq!(clublog.entities.list.len(), 2);
+        assert_eq!(clublog.zone_exceptions.list.len(), 1);
+
+        // Reader providing a single byte at once
+        let clublog =
+            ClubLog::from_reader(BufReader::with_capacity(1, compressed.as_slice())).unwrap();
+        assert_eq!(clublog.entities.list.len(), 2);
+        assert_eq!(
+            ClubLog::date_from_reader(BufReader::with_capacity(1, compressed.as_slice())).unwrap(),
+            clublog.date
+        );
+        let clublog = ClubLog::from_reader(BufReader::with_capacity(1, SAMPLE.as_bytes())).unwrap();
+        assert_eq!(clublog.entities.list.len(), 2);
+    }
+
+    #[test]
+    fn serialize_roundtrip() {
+        let content = SAMPLE.replace("CANADA</name>", "CANADA &amp; &lt;MORE&gt;</name>");
+        let clublog = ClubLog::parse(&content).unwrap();
+
+        let xml = clublog.to_xml().unwrap();
+        assert!(xml.contains("<prefix record=\"10\">"));
+        assert!(xml.contains("<start>2000-01-01T00:00:00+00:00</start>"));
+
+        let reparsed = ClubLog::parse(&xml).unwrap();
+        assert_eq!(reparsed, clublog);
+        assert_eq!(reparsed.entities.list[1].name, "CANADA & <MORE>");
+    }
+
+    #[test]
+    fn parse_error_io() {
+        let res = ClubLog::from_path("data/clublog/does-not-exist.xml");
+        assert!(matches!(res, Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn parse_error_number() {
+        let content = SAMPLE.replace("<adif>1</adif><cqz>5", "<adif>X</adif><cqz>5");
+
+        match ClubLog::parse(&content) {
+            Err(Error::InvalidNumber { value, location }) => {
+                assert_eq!(value, "X");
+                assert_eq!(location.path, "clublog/exceptions/exception/adif");
+                assert_eq!(location.record, Some(1));
+                assert_eq!(location.line, 8);
+            }
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn parse_error_timestamp() {
+        let content = SAMPLE.replace("<start>2002-01-01T00:00:00+00:00", "<start>2002-01-01");
+
+        match ClubLog::parse(&content) {
+            Err(Error::InvalidTimestamp {
+                value, location, ..
+            }) => {
+                assert_eq!(value, "2002-01-01");
+                assert_eq!(
+                    location.path,
+                    "clublog/zone_exceptions/zone_exception/start"
+                );
+                assert_eq!(location.record, Some(30));
+            }
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn parse_error_missing_element() {
+        let content = SAMPLE.replace("<call>DL0XX</call>", "");
+
+        match ClubLog::parse(&content) {
+            Err(Error::MissingElement { element, location }) => {
+                assert_eq!(element, "call");
+                assert_eq!(location.path, "clublog/invalid_operations/invalid");
+                assert_eq!(location.record, Some(20));
+            }
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn parse_error_syntax() {
+        let content = SAMPLE.replace("</prefixes>", "</prefix>");
+
+        match ClubLog::parse(&content) {
+            Err(Error::Xml { location, .. }) => {
+                assert_eq!(location.path, "clublog/prefixes");
+                assert_eq!(location.line, 13);
+            }
+            res => panic!("Unexpected result: {:?}", res),
+        }
     }
 }
@@ -0,0 +1,385 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Comparison of two versions of the ClubLog data.
+//!
+//! Since the ClubLog data is not versioned except for its timestamp, [diff] helps to review the changes of a new release before using it.
+//! Entities are identified by their ADIF identifier, all other entries by their record identifier.
+
+use crate::clublog::{
+    Adif, CallsignException, ClubLog, Entity, InvalidOperation, Prefix, RecordId, ZoneException,
+};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Change of a single field of an entry
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FieldChange {
+    /// Name of the field like it is used within the XML file
+    pub field: &'static str,
+    /// Old value, empty if not present
+    pub old: String,
+    /// New value, empty if not present
+    pub new: String,
+}
+
+/// Modified entry together with the list of changed fields
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Modification<K> {
+    /// Identifier of the entry
+    pub key: K,
+    /// List of changed fields
+    pub changes: Vec<FieldChange>,
+}
+
+/// Differences of a single list like the list of prefixes.
+/// All lists are sorted by the identifier of the entries.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ListDiff<K> {
+    /// Identifiers of added entries
+    pub added: Vec<K>,
+    /// Identifiers of removed entries
+    pub removed: Vec<K>,
+    /// Modified entries
+    pub modified: Vec<Modification<K>>,
+}
+
+impl<K> ListDiff<K> {
+    /// Check if the list did not change.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// True if nothing was added, removed or modified
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Differences between two versions of the ClubLog data
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ClubLogDiff {
+    /// Timestamp of the old data
+    pub old_date: DateTime<Utc>,
+    /// Timestamp of the new data
+    pub new_date: DateTime<Utc>,
+    /// Differences of the entities, identified by the ADIF identifier
+    pub entities: ListDiff<Adif>,
+    /// Differences of the callsign exceptions
+    pub exceptions: ListDiff<RecordId>,
+    /// Differences of the prefixes
+    pub prefixes: ListDiff<RecordId>,
+    /// Differences of the invalid operations
+    pub invalid_operations: ListDiff<RecordId>,
+    /// Differences of the CQ zone exceptions
+    pub zone_exceptions: ListDiff<RecordId>,
+}
+
+impl ClubLogDiff {
+    /// Check if the data did not change, apart from the timestamp.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// True if no entry was added, removed or modified
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+            && self.exceptions.is_empty()
+            && self.prefixes.is_empty()
+            && self.invalid_operations.is_empty()
+            && self.zone_exceptions.is_empty()
+    }
+}
+
+impl fmt::Display for ClubLogDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "ClubLog data {} -> {}",
+            self.old_date.to_rfc3339(),
+            self.new_date.to_rfc3339()
+        )?;
+        write_list(f, "entity", &self.entities)?;
+        write_list(f, "exception", &self.exceptions)?;
+        write_list(f, "prefix", &self.prefixes)?;
+        write_list(f, "invalid", &self.invalid_operations)?;
+        write_list(f, "zone_exception", &self.zone_exceptions)
+    }
+}
+
+/// Write the differences of a single list in a human readable form.
+///
+/// # Arguments
+///
+/// - `f`: Formatter
+/// - `name`: Name of the entries
+/// - `diff`: Differences of the list
+///
+/// # Returns
+///
+/// Formatting result
+fn write_list<K: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    name: &str,
+    diff: &ListDiff<K>,
+) -> fmt::Result {
+    for key in diff.added.iter() {
+        writeln!(f, "+ {} {}", name, key)?;
+    }
+    for key in diff.removed.iter() {
+        writeln!(f, "- {} {}", name, key)?;
+    }
+    for modification in diff.modified.iter() {
+        for change in modification.changes.iter() {
+            writeln!(
+                f,
+                "~ {} {}: {} '{}' -> '{}'",
+                name, modification.key, change.field, change.old, change.new
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Compare two versions of the ClubLog data.
+///
+/// # Arguments
+///
+/// - `old`: Old version of the data
+/// - `new`: New version of the data
+///
+/// # Returns
+///
+/// Differences between both versions
+pub fn diff(old: &ClubLog, new: &ClubLog) -> ClubLogDiff {
+    ClubLogDiff {
+        old_date: old.date,
+        new_date: new.date,
+        entities: diff_list(&old.entities.list, &new.entities.list, |e| e.adif),
+        exceptions: diff_list(&old.exceptions.list, &new.exceptions.list, |e| e.record),
+        prefixes: diff_list(&old.prefixes.list, &new.prefixes.list, |p| p.record),
+        invalid_operations: diff_list(
+            &old.invalid_operations.list,
+            &new.invalid_operations.list,
+            |o| o.record,
+        ),
+        zone_exceptions: diff_list(&old.zone_exceptions.list, &new.zone_exceptions.list, |z| {
+            z.record
+        }),
+    }
+}
+
+/// Compare two versions of a single list.
+///
+/// # Arguments
+///
+/// - `old`: Old version of the list
+/// - `new`: New version of the list
+/// - `key`: Function to get the identifier of an entry
+///
+/// # Returns
+///
+/// Differences between both versions
+fn diff_list<T: Fields, K: Ord + Copy>(old: &[T], new: &[T], key: fn(&T) -> K) -> ListDiff<K> {
+    let old: BTreeMap<K, &T> = old.iter().map(|e| (key(e), e)).collect();
+    let new: BTreeMap<K, &T> = new.iter().map(|e| (key(e), e)).collect();
+
+    let added = new
+        .keys()
+        .filter(|k| !old.contains_key(k))
+        .copied()
+        .collect();
+    let removed = old
+        .keys()
+        .filter(|k| !new.contains_key(k))
+        .copied()
+        .collect();
+
+    let modified = old
+        .iter()
+        .filter_map(|(k, o)| {
+            let n = new.get(k)?;
+            let changes: Vec<FieldChange> = o
+                .fields()
+                .into_iter()
+                .zip(n.fields())
+                .filter(|(o, n)| o.1 != n.1)
+                .map(|(o, n)| FieldChange {
+                    field: o.0,
+                    old: o.1,
+                    new: n.1,
+                })
+                .collect();
+
+            if changes.is_empty() {
+                None
+            } else {
+                Some(Modification { key: *k, changes })
+            }
+        })
+        .collect();
+
+    ListDiff {
+        added,
+        removed,
+        modified,
+    }
+}
+
+/// Access to the fields of an entry as strings to compare them
+trait Fields {
+    /// Get all fields of the entry.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// List of field names together with the formatted values, always in the same order
+    fn fields(&self) -> Vec<(&'static str, String)>;
+}
+
+/// Format an optional value, an absent value results in an empty string
+fn opt<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Format an optional timestamp, an absent value results in an empty string
+fn opt_date(value: &Option<DateTime<Utc>>) -> String {
+    value.map(|v| v.to_rfc3339()).unwrap_or_default()
+}
+
+impl Fields for Entity {
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("name", self.name.clone()),
+            ("prefix", self.prefix.clone()),
+            ("deleted", self.deleted.to_string()),
+            ("cqz", opt(&self.cqz)),
+            ("cont", opt(&self.cont)),
+            ("long", opt(&self.long)),
+            ("lat", opt(&self.lat)),
+            ("start", opt_date(&self.start)),
+            ("end", opt_date(&self.end)),
+            ("whitelist", opt(&self.whitelist)),
+            ("whitelist_start", opt_date(&self.whitelist_start)),
+            ("whitelist_end", opt_date(&self.whitelist_end)),
+        ]
+    }
+}
+
+impl Fields for CallsignException {
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("call", self.call.clone()),
+            ("entity", self.entity.clone()),
+            ("adif", self.adif.to_string()),
+            ("cqz", opt(&self.cqz)),
+            ("cont", opt(&self.cont)),
+            ("long", opt(&self.long)),
+            ("lat", opt(&self.lat)),
+            ("start", opt_date(&self.start)),
+            ("end", opt_date(&self.end)),
+        ]
+    }
+}
+
+impl Fields for Prefix {
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("call", self.call.clone()),
+            ("entity", self.entity.clone()),
+            ("adif", self.adif.to_string()),
+            ("cqz", opt(&self.cqz)),
+            ("cont", opt(&self.cont)),
+            ("long", opt(&self.long)),
+            ("lat", opt(&self.lat)),
+            ("start", opt_date(&self.start)),
+            ("end", opt_date(&self.end)),
+        ]
+    }
+}
+
+impl Fields for InvalidOperation {
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("call", self.call.clone()),
+            ("start", opt_date(&self.start)),
+            ("end", opt_date(&self.end)),
+        ]
+    }
+}
+
+impl Fields for ZoneException {
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("call", self.call.clone()),
+            ("zone", self.zone.to_string()),
+            ("start", opt_date(&self.start)),
+            ("end", opt_date(&self.end)),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = include_str!("../tests/data/cty_sample.xml");
+
+    #[test]
+    fn diff_unchanged() {
+        let clublog = ClubLog::parse(SAMPLE).unwrap();
+        assert!(diff(&clublog, &clublog).is_empty());
+    }
+
+    #[test]
+    fn diff_changes() {
+        let old = ClubLog::parse(SAMPLE).unwrap();
+        let mut new = old.clone();
+
+        new.prefixes.list.retain(|p| p.record != 10);
+        new.zone_exceptions.list[0].zone = 3;
+        new.entities.list[1].end = Some(
+            DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+                .unwrap()
+                .into(),
+        );
+        let mut added = new.exceptions.list[0].clone();
+        added.record = 2;
+        new.exceptions.list.push(added);
+
+        let res = diff(&old, &new);
+
+        assert_eq!(res.prefixes.removed, vec![10]);
+        assert_eq!(res.exceptions.added, vec![2]);
+        assert_eq!(
+            res.zone_exceptions.modified,
+            vec![Modification {
+                key: 30,
+                changes: vec![FieldChange {
+                    field: "zone",
+                    old: String::from("2"),
+                    new: String::from("3"),
+                }],
+            }]
+        );
+        assert_eq!(res.entities.modified[0].key, 1);
+        assert_eq!(res.entities.modified[0].changes[0].field, "end");
+        assert_eq!(res.entities.modified[0].changes[0].old, "");
+        assert!(res.invalid_operations.is_empty());
+
+        let text = res.to_string();
+        assert!(text.contains("- prefix 10"));
+        assert!(text.contains("~ zone_exception 30: zone '2' -> '3'"));
+    }
+}
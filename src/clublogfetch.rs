@@ -0,0 +1,378 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Download of the ClubLog data with a local cache.
+//!
+//! The [Fetcher] downloads the `cty.xml.gz` with an API key and stores it within a cache directory.
+//! On subsequent calls the cached file is reused as long as it is not older than the configured maximum age.
+//! Otherwise the file is requested again with the HTTP caching headers `If-None-Match` and `If-Modified-Since` of the last download.
+//! A downloaded file replaces the cached one only if the timestamp of its data is newer.
+//!
+//! Next to the data file `cty.xml.gz`, the cache directory contains the file `cty.xml.gz.meta` with the caching headers and the timestamp of the cached data.
+
+use crate::clublog::{self, ClubLog};
+use crate::clublogmap::ClubLogMap;
+use chrono::{DateTime, Utc};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+
+/// URL of the ClubLog data download
+pub const DEFAULT_BASE_URL: &str = "https://cdn.clublog.org/cty.php";
+
+/// Name of the cached data file
+const CACHE_FILE: &str = "cty.xml.gz";
+
+/// Name of the file with the metadata of the cached data file
+const META_FILE: &str = "cty.xml.gz.meta";
+
+/// Possible errors while downloading the ClubLog data
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Failed to access the cache directory
+    #[error("Failed to access the cache: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Request failed
+    #[error("Request failed: {0}")]
+    Http(#[source] Box<ureq::Error>),
+
+    /// Server responded with an unexpected status code
+    #[error("Unexpected HTTP status {0}")]
+    Status(u16),
+
+    /// Failed to parse the downloaded or cached data
+    #[error(transparent)]
+    Parse(#[from] clublog::Error),
+}
+
+/// Outcome of a refresh of the cache
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Refresh {
+    /// Cached data is not older than the maximum age, no request was sent
+    Cached,
+    /// Remote data is not newer than the cached data
+    NotModified,
+    /// Newer data was downloaded and stored within the cache
+    Downloaded,
+}
+
+/// Metadata of the cached data file
+#[derive(Debug, Default, PartialEq)]
+struct Meta {
+    /// Timestamp of the cached data
+    date: Option<DateTime<Utc>>,
+    /// Value of the `ETag` header of the last download
+    etag: Option<String>,
+    /// Value of the `Last-Modified` header of the last download
+    last_modified: Option<String>,
+}
+
+impl Meta {
+    /// Parse the metadata file.
+    /// Unknown or invalid lines are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// - `content`: Content of the metadata file
+    ///
+    /// # Returns
+    ///
+    /// Metadata
+    fn parse(content: &str) -> Self {
+        let mut meta = Meta::default();
+
+        for (key, value) in content.lines().filter_map(|l| l.split_once(": ")) {
+            match key {
+                "date" => meta.date = DateTime::parse_from_rfc3339(value).ok().map(|d| d.into()),
+                "etag" => meta.etag = Some(String::from(value)),
+                "last-modified" => meta.last_modified = Some(String::from(value)),
+                _ => (),
+            }
+        }
+
+        meta
+    }
+
+    /// Format the metadata file.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Content of the metadata file
+    fn format(&self) -> String {
+        let mut content = String::new();
+
+        if let Some(date) = self.date {
+            content += &format!("date: {}\n", date.to_rfc3339());
+        }
+        if let Some(etag) = &self.etag {
+            content += &format!("etag: {}\n", etag);
+        }
+        if let Some(last_modified) = &self.last_modified {
+            content += &format!("last-modified: {}\n", last_modified);
+        }
+
+        content
+    }
+}
+
+/// Client to download the ClubLog data into a cache directory
+#[derive(Debug)]
+pub struct Fetcher {
+    /// ClubLog API key
+    api_key: String,
+    /// URL of the download
+    base_url: String,
+    /// Directory to store the downloaded data in
+    cache_dir: PathBuf,
+    /// Maximum age of the cached data before checking for newer data
+    max_age: Option<Duration>,
+    /// HTTP client
+    agent: ureq::Agent,
+}
+
+impl Fetcher {
+    /// Create a new client using the [default URL](DEFAULT_BASE_URL).
+    /// Without a [maximum age](Fetcher::with_max_age), every refresh sends a request.
+    ///
+    /// # Arguments
+    ///
+    /// - `api_key`: ClubLog API key
+    /// - `cache_dir`: Directory to store the downloaded data in, created if not existing
+    ///
+    /// # Returns
+    ///
+    /// Client
+    pub fn new<P: Into<PathBuf>>(api_key: &str, cache_dir: P) -> Self {
+        Fetcher {
+            api_key: String::from(api_key),
+            base_url: String::from(DEFAULT_BASE_URL),
+            cache_dir: cache_dir.into(),
+            max_age: None,
+            agent: ureq::Agent::new(),
+        }
+    }
+
+    /// Set the URL of the download.
+    /// The API key is appended as query parameter `api`.
+    ///
+    /// # Arguments
+    ///
+    /// - `base_url`: URL of the download
+    ///
+    /// # Returns
+    ///
+    /// Modified client
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = String::from(base_url);
+        self
+    }
+
+    /// Set the maximum age of the cached data.
+    /// As long as the cached data file is younger, no request is sent at all.
+    ///
+    /// # Arguments
+    ///
+    /// - `max_age`: Maximum age of the cached data
+    ///
+    /// # Returns
+    ///
+    /// Modified client
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Get the path of the cached data file.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Path of the cached data file, which may not exist yet
+    pub fn cache_path(&self) -> PathBuf {
+        self.cache_dir.join(CACHE_FILE)
+    }
+
+    /// Update the cached data if newer data is available.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Outcome of the refresh or an error
+    pub fn refresh(&self) -> Result<Refresh, Error> {
+        let cache_path = self.cache_path();
+        let meta_path = self.cache_dir.join(META_FILE);
+
+        let cached = cache_path.is_file();
+        if cached && self.is_fresh()? {
+            return Ok(Refresh::Cached);
+        }
+
+        let meta = match cached {
+            true => Meta::parse(&fs::read_to_string(&meta_path).unwrap_or_default()),
+            false => Meta::default(),
+        };
+
+        let mut request = self.agent.get(&self.base_url).query("api", &self.api_key);
+        if let Some(etag) = &meta.etag {
+            request = request.set("If-None-Match", etag);
+        }
+        if let Some(last_modified) = &meta.last_modified {
+            request = request.set("If-Modified-Since", last_modified);
+        }
+
+        let response = match request.call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(status, _)) => return Err(Error::Status(status)),
+            Err(e) => return Err(Error::Http(Box::new(e))),
+        };
+
+        match response.status() {
+            200 => (),
+            304 => {
+                touch(&cache_path)?;
+                return Ok(Refresh::NotModified);
+            }
+            status => return Err(Error::Status(status)),
+        }
+
+        let new_meta = Meta {
+            date: None,
+            etag: response.header("ETag").map(String::from),
+            last_modified: response.header("Last-Modified").map(String::from),
+        };
+
+        let mut data = Vec::new();
+        response.into_reader().read_to_end(&mut data)?;
+        let date = ClubLog::date_from_reader(data.as_slice())?;
+
+        fs::create_dir_all(&self.cache_dir)?;
+
+        if cached && meta.date.is_some_and(|d| d >= date) {
+            touch(&cache_path)?;
+            fs::write(
+                &meta_path,
+                Meta {
+                    date: meta.date,
+                    ..new_meta
+                }
+                .format(),
+            )?;
+            return Ok(Refresh::NotModified);
+        }
+
+        // Replace the cached file atomically to never leave a partially written file behind
+        let tmp_path = self.cache_dir.join(format!("{}.tmp", CACHE_FILE));
+        fs::write(&tmp_path, &data)?;
+        fs::rename(&tmp_path, &cache_path)?;
+        fs::write(
+            &meta_path,
+            Meta {
+                date: Some(date),
+                ..new_meta
+            }
+            .format(),
+        )?;
+
+        Ok(Refresh::Downloaded)
+    }
+
+    /// Refresh the cached data and parse it.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Parsed ClubLog data or an error
+    pub fn fetch(&self) -> Result<ClubLogMap, Error> {
+        self.refresh()?;
+        self.fetch_cached()
+    }
+
+    /// Parse the cached data without checking for newer data, e.g. while being offline.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Parsed ClubLog data or an error, also if nothing was downloaded yet
+    pub fn fetch_cached(&self) -> Result<ClubLogMap, Error> {
+        Ok(ClubLogMap::from(ClubLog::from_path(self.cache_path())?))
+    }
+
+    /// Check whether the cached data file is younger than the maximum age.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// True if a maximum age is set and the file is younger, false otherwise or an error
+    fn is_fresh(&self) -> Result<bool, Error> {
+        let max_age = match self.max_age {
+            Some(max_age) => max_age,
+            None => return Ok(false),
+        };
+
+        let modified = fs::metadata(self.cache_path())?.modified()?;
+        Ok(SystemTime::now()
+            .duration_since(modified)
+            .is_ok_and(|age| age < max_age))
+    }
+}
+
+/// Set the modification time of a file to now.
+///
+/// # Arguments
+///
+/// - `path`: Path of the file
+///
+/// # Returns
+///
+/// Nothing or an error
+fn touch(path: &Path) -> Result<(), Error> {
+    File::options()
+        .append(true)
+        .open(path)?
+        .set_modified(SystemTime::now())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meta_roundtrip() {
+        let meta = Meta {
+            date: Some(
+                DateTime::parse_from_rfc3339("2023-11-25T20:31:27+00:00")
+                    .unwrap()
+                    .into(),
+            ),
+            etag: Some(String::from("\"abc\"")),
+            last_modified: Some(String::from("Sat, 25 Nov 2023 20:31:27 GMT")),
+        };
+
+        assert_eq!(Meta::parse(&meta.format()), meta);
+        assert_eq!(Meta::parse("invalid\ndate: xyz\n"), Meta::default());
+    }
+}
@@ -0,0 +1,278 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Shareable handle to replace the ClubLog data at runtime.
+//!
+//! Long running applications may hold the ClubLog data for weeks, while new releases are published regularly.
+//! A [ClubLogHandle] is shared between threads and allows to replace the data atomically.
+//! Each analysis works on a [snapshot](ClubLogHandle::snapshot) of the data, therefore analyses in progress while replacing the data keep using the old one.
+//!
+//! ```no_run
+//! use chrono::Utc;
+//! use hamcall::call::analyze_callsign;
+//! use hamcall::clubloghandle::ClubLogHandle;
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! let handle = Arc::new(ClubLogHandle::from_path("data/clublog/cty.xml").unwrap());
+//! let _watcher = ClubLogHandle::watch(&handle, "data/clublog/cty.xml", Duration::from_secs(60), |_| {});
+//!
+//! let data = handle.snapshot();
+//! let call = analyze_callsign(&data, "DL1ABC", &Utc::now());
+//! ```
+
+use crate::clublog::{self, ClubLog};
+use crate::clublogmap::ClubLogMap;
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+/// Thread-safe handle to ClubLog data that can be replaced at runtime
+#[derive(Debug)]
+pub struct ClubLogHandle<Q = ClubLogMap> {
+    /// Current data
+    current: RwLock<Arc<Q>>,
+}
+
+impl<Q> ClubLogHandle<Q> {
+    /// Create a new handle.
+    ///
+    /// # Arguments
+    ///
+    /// - `data`: Initial data
+    ///
+    /// # Returns
+    ///
+    /// Handle
+    pub fn new(data: Q) -> Self {
+        ClubLogHandle {
+            current: RwLock::new(Arc::new(data)),
+        }
+    }
+
+    /// Get the current data.
+    /// The returned snapshot implements [ClubLogQuery](crate::clublogquery::ClubLogQuery) and stays valid even if the data is replaced in the meantime.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Snapshot of the current data
+    pub fn snapshot(&self) -> Arc<Q> {
+        self.current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Replace the data atomically.
+    ///
+    /// # Arguments
+    ///
+    /// - `data`: New data
+    ///
+    /// # Returns
+    ///
+    /// Snapshot of the replaced data
+    pub fn replace(&self, data: Q) -> Arc<Q> {
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        std::mem::replace(&mut *current, Arc::new(data))
+    }
+}
+
+impl ClubLogHandle<ClubLogMap> {
+    /// Create a new handle from a ClubLog data file.
+    ///
+    /// # Arguments
+    ///
+    /// - `path`: Path of the data file
+    ///
+    /// # Returns
+    ///
+    /// Handle or an error
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, clublog::Error> {
+        Ok(ClubLogHandle::new(ClubLogMap::from(ClubLog::from_path(
+            path,
+        )?)))
+    }
+
+    /// Replace the data by the content of a ClubLog data file.
+    /// The file is parsed completely before replacing the data, on failure the current data is kept.
+    ///
+    /// # Arguments
+    ///
+    /// - `path`: Path of the data file
+    ///
+    /// # Returns
+    ///
+    /// Timestamp of the new data or an error
+    pub fn reload<P: AsRef<Path>>(&self, path: P) -> Result<DateTime<Utc>, clublog::Error> {
+        let data = ClubLogMap::from(ClubLog::from_path(path)?);
+        let date = data.date();
+        self.replace(data);
+
+        Ok(date)
+    }
+
+    /// Watch a ClubLog data file and reload the data whenever the file is modified.
+    /// The file is checked periodically by its modification time and size within a background thread.
+    /// A new file should replace the old one atomically, e.g. by renaming it, otherwise a partially written file may be read.
+    /// The thread ends when the returned [Watcher] is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// - `handle`: Handle to update
+    /// - `path`: Path of the data file
+    /// - `interval`: Time between two checks of the file
+    /// - `on_reload`: Called with the outcome of every reload, failed reloads keep the current data
+    ///
+    /// # Returns
+    ///
+    /// Watcher of the file
+    pub fn watch<P, F>(handle: &Arc<Self>, path: P, interval: Duration, mut on_reload: F) -> Watcher
+    where
+        P: Into<PathBuf>,
+        F: FnMut(Result<DateTime<Utc>, clublog::Error>) + Send + 'static,
+    {
+        let handle = Arc::clone(handle);
+        let path = path.into();
+        let (stop, stopped) = mpsc::channel::<()>();
+        let mut last = file_state(&path);
+
+        let thread = thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                let state = file_state(&path);
+                if state.is_some() && state != last {
+                    last = state;
+                    on_reload(handle.reload(&path));
+                }
+            }
+        });
+
+        Watcher {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+/// Background thread watching a ClubLog data file, see [ClubLogHandle::watch]
+#[derive(Debug)]
+pub struct Watcher {
+    /// Dropped to stop the thread
+    stop: Option<Sender<()>>,
+    /// Thread watching the file
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Get the modification time and size of a file.
+///
+/// # Arguments
+///
+/// - `path`: Path of the file
+///
+/// # Returns
+///
+/// Modification time and size, `None` if not accessible
+fn file_state(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::call::analyze_callsign;
+    use std::fs::File;
+    use std::sync::Mutex;
+
+    const SAMPLE: &str = include_str!("../tests/data/cty_sample.xml");
+    const NEWER: &str = "2023-12-01T00:00:00+00:00";
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("hamcall-{}-{}.xml", name, std::process::id()))
+    }
+
+    #[test]
+    fn replace_keeps_snapshot() {
+        let handle = ClubLogHandle::new(ClubLogMap::from(ClubLog::parse(SAMPLE).unwrap()));
+        let timestamp = Utc::now();
+
+        let old = handle.snapshot();
+        let newer = ClubLog::parse(&SAMPLE.replace("2023-11-25T20:31:27+00:00", NEWER)).unwrap();
+        let replaced = handle.replace(ClubLogMap::from(newer));
+
+        assert!(Arc::ptr_eq(&old, &replaced));
+        assert_eq!(old.date().to_rfc3339(), "2023-11-25T20:31:27+00:00");
+        assert_eq!(handle.snapshot().date().to_rfc3339(), NEWER);
+        assert_eq!(
+            analyze_callsign(&old, "DL1ABC", &timestamp).unwrap().adif,
+            230
+        );
+    }
+
+    #[test]
+    fn reload_and_watch() {
+        let path = temp_file("reload");
+        fs::write(&path, SAMPLE).unwrap();
+
+        let handle = Arc::new(ClubLogHandle::from_path(&path).unwrap());
+
+        // Failed reload keeps the data
+        assert!(handle.reload(temp_file("missing")).is_err());
+        assert_eq!(
+            handle.snapshot().date().to_rfc3339(),
+            "2023-11-25T20:31:27+00:00"
+        );
+
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let res = Arc::clone(&results);
+        let watcher = ClubLogHandle::watch(&handle, &path, Duration::from_millis(10), move |r| {
+            res.lock().unwrap().push(r.map(|d| d.to_rfc3339()).ok())
+        });
+
+        // Replace the file atomically, like a download should do it
+        let tmp_path = temp_file("reload-tmp");
+        fs::write(
+            &tmp_path,
+            SAMPLE.replace("2023-11-25T20:31:27+00:00", NEWER),
+        )
+        .unwrap();
+        File::options()
+            .append(true)
+            .open(&tmp_path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        fs::rename(&tmp_path, &path).unwrap();
+
+        for _ in 0..200 {
+            if !results.lock().unwrap().is_empty() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        drop(watcher);
+
+        assert_eq!(*results.lock().unwrap(), vec![Some(String::from(NEWER))]);
+        assert_eq!(handle.snapshot().date().to_rfc3339(), NEWER);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
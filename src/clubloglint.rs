@@ -0,0 +1,395 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Consistency checks for the ClubLog data.
+//!
+//! The ClubLog data contains some redundant information, like invalid callsigns that are part of both the [callsign exceptions](crate::clublog::CallsignException) and the [invalid operations](crate::clublog::InvalidOperation).
+//! The function [lint] checks a parsed data set for inconsistencies like that, which may indicate a broken release of the data file.
+
+use crate::clublog::{Adif, ClubLog, RecordId, ADIF_ID_NO_DXCC, CALLSIGN_EXCEPTION_INVALID};
+use crate::clublogquery::time_windows_overlap;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+/// Reference to an entry of the ClubLog data
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RecordRef {
+    /// Entity by ADIF identifier
+    Entity(Adif),
+    /// Callsign exception by record identifier
+    Exception(RecordId),
+    /// Prefix by record identifier
+    Prefix(RecordId),
+    /// Invalid operation by record identifier
+    InvalidOperation(RecordId),
+    /// CQ zone exception by record identifier
+    ZoneException(RecordId),
+}
+
+impl fmt::Display for RecordRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordRef::Entity(adif) => write!(f, "entity {}", adif),
+            RecordRef::Exception(record) => write!(f, "exception {}", record),
+            RecordRef::Prefix(record) => write!(f, "prefix {}", record),
+            RecordRef::InvalidOperation(record) => write!(f, "invalid operation {}", record),
+            RecordRef::ZoneException(record) => write!(f, "zone exception {}", record),
+        }
+    }
+}
+
+/// Inconsistency found within the ClubLog data
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Issue {
+    /// Entry references an ADIF identifier that is not part of the entity list
+    UnknownAdif {
+        /// Entry with the reference
+        record: RecordRef,
+        /// Unknown ADIF identifier
+        adif: Adif,
+    },
+
+    /// Two entries of the same list for the same callsign or prefix have overlapping time windows
+    OverlappingTimeWindows {
+        /// Callsign or prefix
+        call: String,
+        /// First entry
+        first: RecordRef,
+        /// Second entry
+        second: RecordRef,
+    },
+
+    /// Callsign exception for an invalid callsign without a matching invalid operation
+    InvalidExceptionWithoutOperation {
+        /// Record identifier of the callsign exception
+        record: RecordId,
+        /// Callsign
+        call: String,
+    },
+
+    /// Whitelisted entity without any callsign exception
+    WhitelistWithoutExceptions {
+        /// ADIF identifier of the entity
+        adif: Adif,
+    },
+
+    /// Start timestamp of an entry is after its end timestamp
+    StartAfterEnd {
+        /// Entry with the invalid time window
+        record: RecordRef,
+        /// True if the whitelist window of an entity is affected, false if the time window of validity is
+        whitelist: bool,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::UnknownAdif { record, adif } => {
+                write!(f, "{} references unknown ADIF identifier {}", record, adif)
+            }
+            Issue::OverlappingTimeWindows {
+                call,
+                first,
+                second,
+            } => write!(
+                f,
+                "{} and {} for '{}' have overlapping time windows",
+                first, second, call
+            ),
+            Issue::InvalidExceptionWithoutOperation { record, call } => write!(
+                f,
+                "exception {} marks '{}' as invalid, but there is no matching invalid operation",
+                record, call
+            ),
+            Issue::WhitelistWithoutExceptions { adif } => write!(
+                f,
+                "entity {} is whitelisted, but there are no callsign exceptions",
+                adif
+            ),
+            Issue::StartAfterEnd { record, whitelist } => {
+                let window = if *whitelist { "whitelist " } else { "" };
+                write!(
+                    f,
+                    "{} has a {}start timestamp after its {}end timestamp",
+                    record, window, window
+                )
+            }
+        }
+    }
+}
+
+/// Time window of an entry
+type TimeWindow = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+/// Check the ClubLog data for inconsistencies.
+///
+/// # Arguments
+///
+/// - `clublog`: ClubLog data to check
+///
+/// # Returns
+///
+/// List of found inconsistencies, empty if the data is consistent
+pub fn lint(clublog: &ClubLog) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    let adifs: HashSet<Adif> = clublog.entities.list.iter().map(|e| e.adif).collect();
+
+    // Time windows ending before they start
+    let windows = clublog
+        .entities
+        .list
+        .iter()
+        .flat_map(|e| {
+            [
+                (RecordRef::Entity(e.adif), false, (e.start, e.end)),
+                (
+                    RecordRef::Entity(e.adif),
+                    true,
+                    (e.whitelist_start, e.whitelist_end),
+                ),
+            ]
+        })
+        .chain(
+            clublog
+                .exceptions
+                .list
+                .iter()
+                .map(|e| (RecordRef::Exception(e.record), false, (e.start, e.end))),
+        )
+        .chain(
+            clublog
+                .prefixes
+                .list
+                .iter()
+                .map(|p| (RecordRef::Prefix(p.record), false, (p.start, p.end))),
+        )
+        .chain(clublog.invalid_operations.list.iter().map(|o| {
+            (
+                RecordRef::InvalidOperation(o.record),
+                false,
+                (o.start, o.end),
+            )
+        }))
+        .chain(
+            clublog
+                .zone_exceptions
+                .list
+                .iter()
+                .map(|z| (RecordRef::ZoneException(z.record), false, (z.start, z.end))),
+        );
+    for (record, whitelist, window) in windows {
+        if let (Some(start), Some(end)) = window {
+            if start > end {
+                issues.push(Issue::StartAfterEnd { record, whitelist });
+            }
+        }
+    }
+
+    // References to unknown entities
+    for prefix in clublog.prefixes.list.iter() {
+        if prefix.adif != ADIF_ID_NO_DXCC && !adifs.contains(&prefix.adif) {
+            issues.push(Issue::UnknownAdif {
+                record: RecordRef::Prefix(prefix.record),
+                adif: prefix.adif,
+            });
+        }
+    }
+    for exception in clublog.exceptions.list.iter() {
+        if exception.adif != ADIF_ID_NO_DXCC && !adifs.contains(&exception.adif) {
+            issues.push(Issue::UnknownAdif {
+                record: RecordRef::Exception(exception.record),
+                adif: exception.adif,
+            });
+        }
+    }
+
+    // Overlapping time windows for the same callsign or prefix
+    check_overlaps(
+        &mut issues,
+        clublog
+            .exceptions
+            .list
+            .iter()
+            .map(|e| (&e.call, RecordRef::Exception(e.record), (e.start, e.end))),
+    );
+    check_overlaps(
+        &mut issues,
+        clublog
+            .prefixes
+            .list
+            .iter()
+            .map(|p| (&p.call, RecordRef::Prefix(p.record), (p.start, p.end))),
+    );
+    check_overlaps(
+        &mut issues,
+        clublog.invalid_operations.list.iter().map(|o| {
+            (
+                &o.call,
+                RecordRef::InvalidOperation(o.record),
+                (o.start, o.end),
+            )
+        }),
+    );
+    check_overlaps(
+        &mut issues,
+        clublog.zone_exceptions.list.iter().map(|z| {
+            (
+                &z.call,
+                RecordRef::ZoneException(z.record),
+                (z.start, z.end),
+            )
+        }),
+    );
+
+    // Invalid callsigns must be part of both the callsign exceptions and the invalid operations
+    for exception in clublog
+        .exceptions
+        .list
+        .iter()
+        .filter(|e| e.entity == CALLSIGN_EXCEPTION_INVALID)
+    {
+        let found = clublog.invalid_operations.list.iter().any(|o| {
+            o.call == exception.call
+                && time_windows_overlap((o.start, o.end), (exception.start, exception.end))
+        });
+        if !found {
+            issues.push(Issue::InvalidExceptionWithoutOperation {
+                record: exception.record,
+                call: exception.call.clone(),
+            });
+        }
+    }
+
+    // Whitelisted entities require at least a single callsign exception
+    let exception_adifs: HashSet<Adif> = clublog.exceptions.list.iter().map(|e| e.adif).collect();
+    for entity in clublog.entities.list.iter() {
+        if entity.whitelist == Some(true) && !exception_adifs.contains(&entity.adif) {
+            issues.push(Issue::WhitelistWithoutExceptions { adif: entity.adif });
+        }
+    }
+
+    issues
+}
+
+/// Check for entries of the same callsign or prefix with overlapping time windows.
+///
+/// # Arguments
+///
+/// - `issues`: List to append the found issues to
+/// - `entries`: Callsign or prefix, reference and time window of each entry of a single list
+///
+/// # Returns
+///
+/// (None)
+fn check_overlaps<'a>(
+    issues: &mut Vec<Issue>,
+    entries: impl Iterator<Item = (&'a String, RecordRef, TimeWindow)>,
+) {
+    let mut by_call: BTreeMap<&String, Vec<(RecordRef, TimeWindow)>> = BTreeMap::new();
+    for (call, record, window) in entries {
+        by_call.entry(call).or_default().push((record, window));
+    }
+
+    for (call, entries) in by_call.iter().filter(|(_, e)| e.len() > 1) {
+        for (idx, first) in entries.iter().enumerate() {
+            for second in entries[idx + 1..].iter() {
+                if time_windows_overlap(first.1, second.1) {
+                    issues.push(Issue::OverlappingTimeWindows {
+                        call: String::clone(call),
+                        first: first.0,
+                        second: second.0,
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = include_str!("../tests/data/cty_sample.xml");
+
+    #[test]
+    fn lint_consistent() {
+        let clublog = ClubLog::parse(SAMPLE).unwrap();
+        assert_eq!(lint(&clublog), Vec::new());
+    }
+
+    #[test]
+    fn lint_issues() {
+        let mut clublog = ClubLog::parse(SAMPLE).unwrap();
+
+        // Prefix for an unknown entity that also overlaps with an existing one
+        let mut prefix = clublog.prefixes.list[0].clone();
+        prefix.record = 12;
+        prefix.adif = 999;
+        clublog.prefixes.list.push(prefix);
+
+        // Invalid callsign without invalid operation
+        clublog.exceptions.list[0].entity = String::from(CALLSIGN_EXCEPTION_INVALID);
+
+        // Whitelisted entity without exceptions
+        clublog.entities.list[0].whitelist = Some(true);
+
+        // Time windows ending before their start
+        let timestamp = |value| Some(DateTime::parse_from_rfc3339(value).unwrap().into());
+        clublog.invalid_operations.list[0].end = timestamp("2000-01-01T00:00:00Z");
+        clublog.entities.list[1].whitelist_start = timestamp("2010-01-01T00:00:00Z");
+        clublog.entities.list[1].whitelist_end = timestamp("2009-01-01T00:00:00Z");
+
+        let issues = lint(&clublog);
+        assert_eq!(
+            issues,
+            vec![
+                Issue::StartAfterEnd {
+                    record: RecordRef::Entity(1),
+                    whitelist: true
+                },
+                Issue::StartAfterEnd {
+                    record: RecordRef::InvalidOperation(20),
+                    whitelist: false
+                },
+                Issue::UnknownAdif {
+                    record: RecordRef::Prefix(12),
+                    adif: 999
+                },
+                Issue::OverlappingTimeWindows {
+                    call: String::from("DL"),
+                    first: RecordRef::Prefix(10),
+                    second: RecordRef::Prefix(12)
+                },
+                Issue::InvalidExceptionWithoutOperation {
+                    record: 1,
+                    call: String::from("DL0ABC")
+                },
+                Issue::WhitelistWithoutExceptions { adif: 230 },
+            ]
+        );
+    }
+
+    #[test]
+    fn display_issues() {
+        let issue = Issue::StartAfterEnd {
+            record: RecordRef::Entity(1),
+            whitelist: true,
+        };
+        assert_eq!(
+            issue.to_string(),
+            "entity 1 has a whitelist start timestamp after its whitelist end timestamp"
+        );
+        let issue = Issue::StartAfterEnd {
+            record: RecordRef::Entity(1),
+            whitelist: false,
+        };
+        assert_eq!(
+            issue.to_string(),
+            "entity 1 has a start timestamp after its end timestamp"
+        );
+    }
+}
@@ -7,16 +7,29 @@
 use crate::clublog::{
     Adif, CallsignException, ClubLog, CqZone, Entity, InvalidOperation, Prefix, ZoneException,
 };
-use crate::clublogquery::{is_in_time_window, ClubLogQuery};
+use crate::clublogquery::{
+    chronological, entity_name_matches, is_in_time_window, ClubLogQuery, QueryIter,
+};
+use crate::clublogtrie::PrefixTrie;
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 use std::convert::From;
 use std::vec::Vec;
+#[cfg(feature = "snapshot")]
+use {
+    crate::clublog::RecordId,
+    crate::clublogsnapshot::{self, SnapshotReader, SnapshotWriter},
+    std::fs::{self, File},
+    std::io::{BufWriter, Write},
+    std::path::Path,
+};
 
 /// HashMap based implementation of the [ClubLogQuery] trait
+#[derive(Debug, PartialEq)]
 pub struct ClubLogMap {
+    date: DateTime<Utc>,
     entities: HashMap<Adif, Entity>,
-    prefixes: HashMap<String, Vec<Prefix>>,
+    prefixes: PrefixTrie<Prefix>,
     callsign_exceptions: HashMap<String, Vec<CallsignException>>,
     invalid_operations: HashMap<String, Vec<InvalidOperation>>,
     zone_exceptions: HashMap<String, Vec<ZoneException>>,
@@ -38,13 +51,13 @@ impl From<ClubLog> for ClubLogMap {
             }
         }
 
-        let mut prefixes: HashMap<String, Vec<Prefix>> = HashMap::new();
-        for prefix in clublog.prefixes.list.into_iter() {
-            if let Some(value) = prefixes.get_mut(&prefix.call) {
-                value.push(prefix);
-            } else {
-                prefixes.insert(prefix.call.clone(), vec![prefix]);
-            }
+        // Insert the prefixes ordered by their call to get the same trie independent of the order within the data
+        let mut prefix_list = clublog.prefixes.list;
+        prefix_list.sort_by(|a, b| a.call.cmp(&b.call));
+        let mut prefixes: PrefixTrie<Prefix> = PrefixTrie::new();
+        for prefix in prefix_list.into_iter() {
+            let call = prefix.call.clone();
+            prefixes.insert(&call, prefix);
         }
 
         let mut invalid_operations: HashMap<String, Vec<InvalidOperation>> = HashMap::new();
@@ -66,6 +79,7 @@ impl From<ClubLog> for ClubLogMap {
         }
 
         ClubLogMap {
+            date: clublog.date,
             entities,
             callsign_exceptions,
             prefixes,
@@ -75,6 +89,210 @@ impl From<ClubLog> for ClubLogMap {
     }
 }
 
+impl ClubLogMap {
+    /// Get the timestamp of the ClubLog data.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Timestamp of the ClubLog data
+    pub fn date(&self) -> DateTime<Utc> {
+        self.date
+    }
+
+    /// Write the data as a [snapshot](crate::clublogsnapshot).
+    ///
+    /// # Arguments
+    ///
+    /// - `writer`: Writer to write the snapshot to
+    ///
+    /// # Returns
+    ///
+    /// Nothing or an error
+    #[cfg(feature = "snapshot")]
+    pub fn write_snapshot<W: Write>(&self, writer: W) -> Result<(), clublogsnapshot::Error> {
+        let mut snapshot = SnapshotWriter::new(writer, &self.date)?;
+
+        let mut entities: Vec<&Entity> = self.entities.values().collect();
+        entities.sort_by_key(|e| e.adif);
+        snapshot.len(entities.len())?;
+        for entity in entities {
+            snapshot.entity(entity)?;
+        }
+
+        write_groups(
+            &mut snapshot,
+            &self.callsign_exceptions,
+            |e| e.record,
+            SnapshotWriter::callsign_exception,
+        )?;
+
+        snapshot.len(self.prefixes.nodes().count())?;
+        for (children, values) in self.prefixes.nodes() {
+            snapshot.len(children.len())?;
+            for (byte, child) in children {
+                snapshot.child(*byte, *child)?;
+            }
+            snapshot.len(values.len())?;
+            for prefix in values {
+                snapshot.prefix(prefix)?;
+            }
+        }
+
+        write_groups(
+            &mut snapshot,
+            &self.invalid_operations,
+            |o| o.record,
+            SnapshotWriter::invalid_operation,
+        )?;
+        write_groups(
+            &mut snapshot,
+            &self.zone_exceptions,
+            |z| z.record,
+            SnapshotWriter::zone_exception,
+        )?;
+
+        snapshot.finish()
+    }
+
+    /// Load the data from a [snapshot](crate::clublogsnapshot).
+    /// The stored index is restored as it is, so no rebuild of the lookup tables is necessary.
+    /// The snapshot may be any byte slice, e.g. the content of a file or of a mapped file, the records are copied out of it.
+    ///
+    /// # Arguments
+    ///
+    /// - `data`: Content of the snapshot
+    ///
+    /// # Returns
+    ///
+    /// Loaded data or an error
+    #[cfg(feature = "snapshot")]
+    pub fn from_snapshot(data: &[u8]) -> Result<Self, clublogsnapshot::Error> {
+        let mut snapshot = SnapshotReader::new(data)?;
+        let date = snapshot.date()?;
+
+        let mut entities: HashMap<Adif, Entity> = HashMap::new();
+        for _ in 0..snapshot.len()? {
+            let entity = snapshot.entity()?;
+            if entities.insert(entity.adif, entity).is_some() {
+                return Err(clublogsnapshot::Error::Corrupted);
+            }
+        }
+
+        let callsign_exceptions = read_groups(
+            &mut snapshot,
+            |e| &e.call,
+            SnapshotReader::callsign_exception,
+        )?;
+
+        let mut nodes = Vec::new();
+        for _ in 0..snapshot.len()? {
+            let children = (0..snapshot.len()?)
+                .map(|_| snapshot.child())
+                .collect::<Result<Vec<_>, _>>()?;
+            let values = (0..snapshot.len()?)
+                .map(|_| snapshot.prefix())
+                .collect::<Result<Vec<_>, _>>()?;
+            nodes.push((children, values));
+        }
+        let prefixes = PrefixTrie::from_nodes(nodes).ok_or(clublogsnapshot::Error::Corrupted)?;
+
+        let invalid_operations = read_groups(
+            &mut snapshot,
+            |o| &o.call,
+            SnapshotReader::invalid_operation,
+        )?;
+        let zone_exceptions =
+            read_groups(&mut snapshot, |z| &z.call, SnapshotReader::zone_exception)?;
+
+        Ok(ClubLogMap {
+            date,
+            entities,
+            prefixes,
+            callsign_exceptions,
+            invalid_operations,
+            zone_exceptions,
+        })
+    }
+
+    /// Write the data as a [snapshot](crate::clublogsnapshot) into a file.
+    ///
+    /// # Arguments
+    ///
+    /// - `path`: Path of the snapshot file
+    ///
+    /// # Returns
+    ///
+    /// Nothing or an error
+    #[cfg(feature = "snapshot")]
+    pub fn save_snapshot<P: AsRef<Path>>(&self, path: P) -> Result<(), clublogsnapshot::Error> {
+        self.write_snapshot(BufWriter::new(File::create(path)?))
+    }
+
+    /// Load the data from a [snapshot](crate::clublogsnapshot) file.
+    ///
+    /// # Arguments
+    ///
+    /// - `path`: Path of the snapshot file
+    ///
+    /// # Returns
+    ///
+    /// Loaded data or an error
+    #[cfg(feature = "snapshot")]
+    pub fn load_snapshot<P: AsRef<Path>>(path: P) -> Result<Self, clublogsnapshot::Error> {
+        Self::from_snapshot(&fs::read(path)?)
+    }
+}
+
+/// Write records grouped by their callsign, ordered by the record identifier of the first record of each group.
+#[cfg(feature = "snapshot")]
+fn write_groups<W: Write, T>(
+    snapshot: &mut SnapshotWriter<W>,
+    groups: &HashMap<String, Vec<T>>,
+    record: impl Fn(&T) -> RecordId,
+    write: impl Fn(&mut SnapshotWriter<W>, &T) -> Result<(), clublogsnapshot::Error>,
+) -> Result<(), clublogsnapshot::Error> {
+    let mut groups: Vec<&Vec<T>> = groups.values().collect();
+    groups.sort_by_key(|g| g.first().map(&record));
+
+    snapshot.len(groups.len())?;
+    for group in groups {
+        snapshot.len(group.len())?;
+        for value in group {
+            write(snapshot, value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Read records grouped by their callsign as written by [write_groups].
+#[cfg(feature = "snapshot")]
+fn read_groups<'a, T>(
+    snapshot: &mut SnapshotReader<'a>,
+    call: impl Fn(&T) -> &String,
+    read: impl Fn(&mut SnapshotReader<'a>) -> Result<T, clublogsnapshot::Error>,
+) -> Result<HashMap<String, Vec<T>>, clublogsnapshot::Error> {
+    let mut groups: HashMap<String, Vec<T>> = HashMap::new();
+    for _ in 0..snapshot.len()? {
+        let group = (0..snapshot.len()?)
+            .map(|_| read(snapshot))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Each group must be non-empty and consist of records of a single callsign only
+        let key = match group.first() {
+            Some(first) if group.iter().all(|v| call(v) == call(first)) => call(first).clone(),
+            _ => return Err(clublogsnapshot::Error::Corrupted),
+        };
+        if groups.insert(key, group).is_some() {
+            return Err(clublogsnapshot::Error::Corrupted);
+        }
+    }
+    Ok(groups)
+}
+
 impl ClubLogQuery for ClubLogMap {
     fn get_entity(&self, adif: Adif, timestamp: &DateTime<Utc>) -> Option<&Entity> {
         if let Some(entity) = self.entities.get(&adif) {
@@ -87,11 +305,42 @@ impl ClubLogQuery for ClubLogMap {
 
     fn get_prefix(&self, prefix: &str, timestamp: &DateTime<Utc>) -> Option<&Prefix> {
         self.prefixes
-            .get(prefix)?
+            .get(prefix)
             .iter()
             .find(|p| is_in_time_window(timestamp, p.start, p.end))
     }
 
+    fn longest_prefix_match(&self, call: &str, timestamp: &DateTime<Utc>) -> Option<&Prefix> {
+        self.prefixes
+            .path(call)
+            .filter_map(|(_, cursor)| {
+                self.prefixes
+                    .values_at(cursor)
+                    .iter()
+                    .find(|p| is_in_time_window(timestamp, p.start, p.end))
+            })
+            .last()
+    }
+
+    fn longest_prefix_match_with_appendix(
+        &self,
+        call: &str,
+        appendix: &str,
+        timestamp: &DateTime<Utc>,
+    ) -> Option<&Prefix> {
+        self.prefixes
+            .path(call)
+            .filter_map(|(_, cursor)| {
+                let cursor = self.prefixes.descend(cursor, "/")?;
+                let cursor = self.prefixes.descend(cursor, appendix)?;
+                self.prefixes
+                    .values_at(cursor)
+                    .iter()
+                    .find(|p| is_in_time_window(timestamp, p.start, p.end))
+            })
+            .last()
+    }
+
     fn get_callsign_exception(
         &self,
         callsign: &str,
@@ -112,9 +361,109 @@ impl ClubLogQuery for ClubLogMap {
     }
 
     fn is_invalid_operation(&self, callsign: &str, timestamp: &DateTime<Utc>) -> bool {
-        self.invalid_operations.get(callsign).map_or(false, |io| {
+        self.invalid_operations.get(callsign).is_some_and(|io| {
             io.iter()
                 .any(|io| is_in_time_window(timestamp, io.start, io.end))
         })
     }
+
+    fn iter_entities(&self) -> QueryIter<'_, Entity> {
+        Box::new(self.entities.values())
+    }
+
+    fn iter_prefixes(&self) -> QueryIter<'_, Prefix> {
+        Box::new(self.prefixes.values())
+    }
+
+    fn iter_callsign_exceptions(&self) -> QueryIter<'_, CallsignException> {
+        Box::new(self.callsign_exceptions.values().flatten())
+    }
+
+    fn iter_zone_exceptions(&self) -> QueryIter<'_, ZoneException> {
+        Box::new(self.zone_exceptions.values().flatten())
+    }
+
+    fn iter_invalid_operations(&self) -> QueryIter<'_, InvalidOperation> {
+        Box::new(self.invalid_operations.values().flatten())
+    }
+
+    fn supports_history(&self) -> bool {
+        true
+    }
+
+    fn get_entity_by_prefix(&self, prefix: &str, timestamp: &DateTime<Utc>) -> Option<&Entity> {
+        self.entities
+            .values()
+            .find(|e| e.prefix == prefix && is_in_time_window(timestamp, e.start, e.end))
+    }
+
+    fn entities(&self, timestamp: &DateTime<Utc>) -> QueryIter<'_, Entity> {
+        let timestamp = *timestamp;
+        Box::new(
+            self.entities
+                .values()
+                .filter(move |e| is_in_time_window(&timestamp, e.start, e.end)),
+        )
+    }
+
+    fn search_entities<'a>(&'a self, name: &str) -> QueryIter<'a, Entity> {
+        let name = name.to_lowercase();
+        Box::new(
+            self.entities
+                .values()
+                .filter(move |e| entity_name_matches(e, &name)),
+        )
+    }
+
+    fn prefixes_by_adif(&self, adif: Adif) -> QueryIter<'_, Prefix> {
+        Box::new(self.prefixes.values().filter(move |p| p.adif == adif))
+    }
+
+    fn exceptions_by_adif(&self, adif: Adif) -> QueryIter<'_, CallsignException> {
+        Box::new(
+            self.callsign_exceptions
+                .values()
+                .flatten()
+                .filter(move |e| e.adif == adif),
+        )
+    }
+
+    fn prefix_history(&self, prefix: &str) -> Vec<&Prefix> {
+        chronological(self.prefixes.get(prefix).iter().collect(), |p| {
+            (p.start, p.record)
+        })
+    }
+
+    fn callsign_exception_history(&self, callsign: &str) -> Vec<&CallsignException> {
+        chronological(
+            self.callsign_exceptions
+                .get(callsign)
+                .into_iter()
+                .flatten()
+                .collect(),
+            |e| (e.start, e.record),
+        )
+    }
+
+    fn zone_exception_history(&self, callsign: &str) -> Vec<&ZoneException> {
+        chronological(
+            self.zone_exceptions
+                .get(callsign)
+                .into_iter()
+                .flatten()
+                .collect(),
+            |z| (z.start, z.record),
+        )
+    }
+
+    fn invalid_operation_history(&self, callsign: &str) -> Vec<&InvalidOperation> {
+        chronological(
+            self.invalid_operations
+                .get(callsign)
+                .into_iter()
+                .flatten()
+                .collect(),
+            |o| (o.start, o.record),
+        )
+    }
 }
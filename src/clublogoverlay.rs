@@ -0,0 +1,885 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Local overrides on top of the ClubLog data.
+//!
+//! New DXpeditions or known misattributions may require corrections before the next release of the ClubLog data.
+//! An [Overlay] holds such corrections and [ClubLogOverlay] layers them on top of any data implementing [ClubLogQuery].
+//!
+//! An overlay is read either from a XML file in the ClubLog schema, where all lists are optional, or from a simple CSV file.
+//! The CSV file contains one entry per line, empty lines and lines starting with `#` are ignored.
+//! Fields may be enclosed in double quotes to contain commas, empty fields are treated as absent.
+//! The first field selects the type of the entry:
+//!
+//! ```text
+//! exception,<call>,<entity>,<adif>,<cqz>,<cont>,<long>,<lat>,<start>,<end>
+//! prefix,<call>,<entity>,<adif>,<cqz>,<cont>,<long>,<lat>,<start>,<end>
+//! invalid,<call>,<start>,<end>
+//! zone_exception,<call>,<zone>,<start>,<end>
+//! ```
+//!
+//! Timestamps follow RFC 3339, like within the ClubLog XML file.
+//! Since the CSV file does not contain record identifiers, the line number of an entry added to [OVERLAY_RECORD_OFFSET] is used instead.
+//! This keeps them apart from the record identifiers of the ClubLog data.
+//! XML overlays keep the record identifiers of the file and should therefore choose identifiers not used by the ClubLog data.
+
+use crate::clublog::{
+    self, Adif, CallsignException, CallsignExceptions, ClubLog, CqZone, Entities, Entity,
+    InvalidOperation, InvalidOperations, Prefix, Prefixes, RecordId, ZoneException, ZoneExceptions,
+};
+use crate::clublogmap::ClubLogMap;
+use crate::clublogparser;
+use crate::clublogquery::{chronological, time_windows_overlap, ClubLogQuery, QueryIter};
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Offset of the record identifiers assigned to the entries of a CSV overlay
+pub const OVERLAY_RECORD_OFFSET: RecordId = 0x8000_0000;
+
+/// Possible errors while reading an overlay
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Failed to read the file
+    #[error("Failed to read the overlay")]
+    Io(#[from] std::io::Error),
+
+    /// Failed to parse the XML file
+    #[error(transparent)]
+    Xml(#[from] clublog::Error),
+
+    /// Entry of the CSV file does not follow the format
+    #[error("Invalid format in line {line}: {reason}")]
+    InvalidFormat {
+        /// Line of the entry
+        line: usize,
+        /// Description of the problem
+        reason: &'static str,
+    },
+
+    /// Value of a field within the CSV file is invalid
+    #[error("Invalid value '{value}' for field '{field}' in line {line}")]
+    InvalidValue {
+        /// Line of the entry
+        line: usize,
+        /// Name of the field
+        field: &'static str,
+        /// Raw value
+        value: String,
+    },
+}
+
+/// Local corrections of the ClubLog data
+#[derive(Debug, PartialEq)]
+pub struct Overlay {
+    /// Corrections
+    data: ClubLogMap,
+}
+
+impl Overlay {
+    /// Parse an overlay from XML in the ClubLog schema.
+    /// Lists not present within the document are treated like empty ones.
+    ///
+    /// # Arguments
+    ///
+    /// - `content`: XML formatted overlay
+    ///
+    /// # Returns
+    ///
+    /// Parsed overlay or an error
+    pub fn parse_xml(content: &str) -> Result<Self, Error> {
+        let clublog = clublogparser::parse_partial(content.as_bytes())?;
+        Ok(Overlay::from(clublog))
+    }
+
+    /// Parse an overlay from the CSV format described in the [module documentation](self).
+    ///
+    /// # Arguments
+    ///
+    /// - `content`: CSV formatted overlay
+    ///
+    /// # Returns
+    ///
+    /// Parsed overlay or an error
+    pub fn parse_csv(content: &str) -> Result<Self, Error> {
+        let mut clublog = ClubLog {
+            date: DateTime::<Utc>::default(),
+            entities: Entities { list: Vec::new() },
+            exceptions: CallsignExceptions { list: Vec::new() },
+            prefixes: Prefixes { list: Vec::new() },
+            invalid_operations: InvalidOperations { list: Vec::new() },
+            zone_exceptions: ZoneExceptions { list: Vec::new() },
+        };
+
+        for (idx, raw) in content.lines().enumerate() {
+            let line = idx + 1;
+            let raw = raw.trim();
+            if raw.is_empty() || raw.starts_with('#') {
+                continue;
+            }
+
+            let entry = Entry::new(line, split_csv(raw, line)?);
+            let record = OVERLAY_RECORD_OFFSET + line as RecordId;
+
+            match entry.fields[0].as_str() {
+                "exception" => {
+                    entry.expect_len(10)?;
+                    clublog.exceptions.list.push(CallsignException {
+                        record,
+                        call: entry.required(1, "call")?,
+                        entity: entry.required(2, "entity")?,
+                        adif: entry.required(3, "adif")?,
+                        cqz: entry.optional(4, "cqz")?,
+                        cont: entry.optional(5, "cont")?,
+                        long: entry.optional(6, "long")?,
+                        lat: entry.optional(7, "lat")?,
+                        start: entry.timestamp(8, "start")?,
+                        end: entry.timestamp(9, "end")?,
+                    });
+                }
+                "prefix" => {
+                    entry.expect_len(10)?;
+                    clublog.prefixes.list.push(Prefix {
+                        record,
+                        call: entry.required(1, "call")?,
+                        entity: entry.required(2, "entity")?,
+                        adif: entry.required(3, "adif")?,
+                        cqz: entry.optional(4, "cqz")?,
+                        cont: entry.optional(5, "cont")?,
+                        long: entry.optional(6, "long")?,
+                        lat: entry.optional(7, "lat")?,
+                        start: entry.timestamp(8, "start")?,
+                        end: entry.timestamp(9, "end")?,
+                    });
+                }
+                "invalid" => {
+                    entry.expect_len(4)?;
+                    clublog.invalid_operations.list.push(InvalidOperation {
+                        record,
+                        call: entry.required(1, "call")?,
+                        start: entry.timestamp(2, "start")?,
+                        end: entry.timestamp(3, "end")?,
+                    });
+                }
+                "zone_exception" => {
+                    entry.expect_len(5)?;
+                    clublog.zone_exceptions.list.push(ZoneException {
+                        record,
+                        call: entry.required(1, "call")?,
+                        zone: entry.required(2, "zone")?,
+                        start: entry.timestamp(3, "start")?,
+                        end: entry.timestamp(4, "end")?,
+                    });
+                }
+                _ => {
+                    return Err(Error::InvalidFormat {
+                        line,
+                        reason: "unknown entry type",
+                    })
+                }
+            }
+        }
+
+        Ok(Overlay::from(clublog))
+    }
+
+    /// Read an overlay from a file.
+    /// Files with the extension `xml` are parsed as XML, all others as CSV.
+    ///
+    /// # Arguments
+    ///
+    /// - `path`: Path to the file
+    ///
+    /// # Returns
+    ///
+    /// Parsed overlay or an error
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+
+        if path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("xml"))
+        {
+            Overlay::parse_xml(&content)
+        } else {
+            Overlay::parse_csv(&content)
+        }
+    }
+}
+
+impl From<ClubLog> for Overlay {
+    fn from(clublog: ClubLog) -> Self {
+        Overlay {
+            data: ClubLogMap::from(clublog),
+        }
+    }
+}
+
+/// ClubLog data together with local corrections.
+///
+/// Every query is answered by the overlay first and falls back to the underlying data.
+/// Enumerations list the entries of the overlay first, followed by the ones of the underlying data.
+/// Entries of the underlying data are hidden from enumerations and histories, if the overlay contains an entry of the same list for the same key with an overlapping time window.
+/// A callsign exception within the overlay additionally hides invalid operations of the underlying data for the same callsign.
+/// Overlays can be stacked, since the underlying data may be a [ClubLogOverlay] itself.
+#[derive(Debug)]
+pub struct ClubLogOverlay<Q> {
+    /// Underlying data
+    base: Q,
+    /// Corrections taking precedence
+    overlay: Overlay,
+}
+
+impl<Q: ClubLogQuery> ClubLogOverlay<Q> {
+    /// Layer corrections on top of ClubLog data.
+    ///
+    /// # Arguments
+    ///
+    /// - `base`: Underlying data
+    /// - `overlay`: Corrections taking precedence
+    ///
+    /// # Returns
+    ///
+    /// Layered data
+    pub fn new(base: Q, overlay: Overlay) -> Self {
+        ClubLogOverlay { base, overlay }
+    }
+
+    /// Get the underlying data.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Underlying data
+    pub fn base(&self) -> &Q {
+        &self.base
+    }
+
+    /// Get the corrections.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Corrections
+    pub fn overlay(&self) -> &Overlay {
+        &self.overlay
+    }
+
+    /// Check whether a prefix of the underlying data is hidden by the overlay.
+    ///
+    /// # Arguments
+    ///
+    /// - `prefix`: Prefix of the underlying data
+    ///
+    /// # Returns
+    ///
+    /// True if the overlay contains a prefix with the same call and an overlapping time window
+    fn hides_prefix(&self, prefix: &Prefix) -> bool {
+        self.overlay
+            .data
+            .prefix_history(&prefix.call)
+            .iter()
+            .any(|o| time_windows_overlap((o.start, o.end), (prefix.start, prefix.end)))
+    }
+
+    /// Check whether a callsign exception of the underlying data is hidden by the overlay.
+    ///
+    /// # Arguments
+    ///
+    /// - `exception`: Callsign exception of the underlying data
+    ///
+    /// # Returns
+    ///
+    /// True if the overlay contains a callsign exception with the same call and an overlapping time window
+    fn hides_callsign_exception(&self, exception: &CallsignException) -> bool {
+        self.overlay
+            .data
+            .callsign_exception_history(&exception.call)
+            .iter()
+            .any(|o| time_windows_overlap((o.start, o.end), (exception.start, exception.end)))
+    }
+
+    /// Check whether a CQ zone exception of the underlying data is hidden by the overlay.
+    ///
+    /// # Arguments
+    ///
+    /// - `exception`: CQ zone exception of the underlying data
+    ///
+    /// # Returns
+    ///
+    /// True if the overlay contains a CQ zone exception with the same call and an overlapping time window
+    fn hides_zone_exception(&self, exception: &ZoneException) -> bool {
+        self.overlay
+            .data
+            .zone_exception_history(&exception.call)
+            .iter()
+            .any(|o| time_windows_overlap((o.start, o.end), (exception.start, exception.end)))
+    }
+
+    /// Check whether an invalid operation of the underlying data is hidden by the overlay.
+    ///
+    /// # Arguments
+    ///
+    /// - `operation`: Invalid operation of the underlying data
+    ///
+    /// # Returns
+    ///
+    /// True if the overlay contains an invalid operation or a callsign exception with the same call and an overlapping time window
+    fn hides_invalid_operation(&self, operation: &InvalidOperation) -> bool {
+        let window = (operation.start, operation.end);
+        self.overlay
+            .data
+            .invalid_operation_history(&operation.call)
+            .iter()
+            .any(|o| time_windows_overlap((o.start, o.end), window))
+            || self
+                .overlay
+                .data
+                .callsign_exception_history(&operation.call)
+                .iter()
+                .any(|e| time_windows_overlap((e.start, e.end), window))
+    }
+}
+
+impl<Q: ClubLogQuery> ClubLogQuery for ClubLogOverlay<Q> {
+    fn get_entity(&self, adif: Adif, timestamp: &DateTime<Utc>) -> Option<&Entity> {
+        self.overlay
+            .data
+            .get_entity(adif, timestamp)
+            .or_else(|| self.base.get_entity(adif, timestamp))
+    }
+
+    fn get_prefix(&self, prefix: &str, timestamp: &DateTime<Utc>) -> Option<&Prefix> {
+        self.overlay
+            .data
+            .get_prefix(prefix, timestamp)
+            .or_else(|| self.base.get_prefix(prefix, timestamp))
+    }
+
+    fn longest_prefix_match(&self, call: &str, timestamp: &DateTime<Utc>) -> Option<&Prefix> {
+        longer(
+            self.overlay.data.longest_prefix_match(call, timestamp),
+            self.base.longest_prefix_match(call, timestamp),
+        )
+    }
+
+    fn longest_prefix_match_with_appendix(
+        &self,
+        call: &str,
+        appendix: &str,
+        timestamp: &DateTime<Utc>,
+    ) -> Option<&Prefix> {
+        longer(
+            self.overlay
+                .data
+                .longest_prefix_match_with_appendix(call, appendix, timestamp),
+            self.base
+                .longest_prefix_match_with_appendix(call, appendix, timestamp),
+        )
+    }
+
+    fn get_callsign_exception(
+        &self,
+        callsign: &str,
+        timestamp: &DateTime<Utc>,
+    ) -> Option<&CallsignException> {
+        self.overlay
+            .data
+            .get_callsign_exception(callsign, timestamp)
+            .or_else(|| self.base.get_callsign_exception(callsign, timestamp))
+    }
+
+    fn get_zone_exception(&self, callsign: &str, timestamp: &DateTime<Utc>) -> Option<CqZone> {
+        self.overlay
+            .data
+            .get_zone_exception(callsign, timestamp)
+            .or_else(|| self.base.get_zone_exception(callsign, timestamp))
+    }
+
+    fn is_invalid_operation(&self, callsign: &str, timestamp: &DateTime<Utc>) -> bool {
+        if self.overlay.data.is_invalid_operation(callsign, timestamp) {
+            true
+        } else if self
+            .overlay
+            .data
+            .get_callsign_exception(callsign, timestamp)
+            .is_some()
+        {
+            false
+        } else {
+            self.base.is_invalid_operation(callsign, timestamp)
+        }
+    }
+
+    fn iter_entities(&self) -> QueryIter<'_, Entity> {
+        // Entities of the base data are hidden by an entity of the overlay with the same identifier and an overlapping time window
+        Box::new(
+            self.overlay
+                .data
+                .iter_entities()
+                .chain(self.base.iter_entities().filter(move |e| {
+                    !self.overlay.data.iter_entities().any(|o| {
+                        o.adif == e.adif && time_windows_overlap((o.start, o.end), (e.start, e.end))
+                    })
+                })),
+        )
+    }
+
+    fn iter_prefixes(&self) -> QueryIter<'_, Prefix> {
+        Box::new(
+            self.overlay.data.iter_prefixes().chain(
+                self.base
+                    .iter_prefixes()
+                    .filter(move |p| !self.hides_prefix(p)),
+            ),
+        )
+    }
+
+    fn iter_callsign_exceptions(&self) -> QueryIter<'_, CallsignException> {
+        Box::new(
+            self.overlay.data.iter_callsign_exceptions().chain(
+                self.base
+                    .iter_callsign_exceptions()
+                    .filter(move |e| !self.hides_callsign_exception(e)),
+            ),
+        )
+    }
+
+    fn iter_zone_exceptions(&self) -> QueryIter<'_, ZoneException> {
+        Box::new(
+            self.overlay.data.iter_zone_exceptions().chain(
+                self.base
+                    .iter_zone_exceptions()
+                    .filter(move |z| !self.hides_zone_exception(z)),
+            ),
+        )
+    }
+
+    fn iter_invalid_operations(&self) -> QueryIter<'_, InvalidOperation> {
+        Box::new(
+            self.overlay.data.iter_invalid_operations().chain(
+                self.base
+                    .iter_invalid_operations()
+                    .filter(move |o| !self.hides_invalid_operation(o)),
+            ),
+        )
+    }
+
+    fn supports_history(&self) -> bool {
+        // The overlay itself always lists all of its records
+        self.base.supports_history()
+    }
+
+    fn get_entity_by_prefix(&self, prefix: &str, timestamp: &DateTime<Utc>) -> Option<&Entity> {
+        self.overlay
+            .data
+            .get_entity_by_prefix(prefix, timestamp)
+            .or_else(|| self.base.get_entity_by_prefix(prefix, timestamp))
+    }
+
+    fn entities(&self, timestamp: &DateTime<Utc>) -> QueryIter<'_, Entity> {
+        let ts = *timestamp;
+        Box::new(
+            self.overlay.data.entities(timestamp).chain(
+                self.base
+                    .entities(timestamp)
+                    .filter(move |e| self.overlay.data.get_entity(e.adif, &ts).is_none()),
+            ),
+        )
+    }
+
+    fn prefix_history(&self, prefix: &str) -> Vec<&Prefix> {
+        let mut records = self.overlay.data.prefix_history(prefix);
+        records.extend(
+            self.base
+                .prefix_history(prefix)
+                .into_iter()
+                .filter(|p| !self.hides_prefix(p)),
+        );
+        chronological(records, |p| (p.start, p.record))
+    }
+
+    fn callsign_exception_history(&self, callsign: &str) -> Vec<&CallsignException> {
+        let mut records = self.overlay.data.callsign_exception_history(callsign);
+        records.extend(
+            self.base
+                .callsign_exception_history(callsign)
+                .into_iter()
+                .filter(|e| !self.hides_callsign_exception(e)),
+        );
+        chronological(records, |e| (e.start, e.record))
+    }
+
+    fn zone_exception_history(&self, callsign: &str) -> Vec<&ZoneException> {
+        let mut records = self.overlay.data.zone_exception_history(callsign);
+        records.extend(
+            self.base
+                .zone_exception_history(callsign)
+                .into_iter()
+                .filter(|z| !self.hides_zone_exception(z)),
+        );
+        chronological(records, |z| (z.start, z.record))
+    }
+
+    fn invalid_operation_history(&self, callsign: &str) -> Vec<&InvalidOperation> {
+        let mut records = self.overlay.data.invalid_operation_history(callsign);
+        records.extend(
+            self.base
+                .invalid_operation_history(callsign)
+                .into_iter()
+                .filter(|o| !self.hides_invalid_operation(o)),
+        );
+        chronological(records, |o| (o.start, o.record))
+    }
+}
+
+/// Select the longer of two prefixes, the first one takes precedence if both have the same length.
+///
+/// # Arguments
+///
+/// - `first`: First prefix
+/// - `second`: Second prefix
+///
+/// # Returns
+///
+/// Longer prefix, `None` if both are absent
+fn longer<'a>(first: Option<&'a Prefix>, second: Option<&'a Prefix>) -> Option<&'a Prefix> {
+    match (first, second) {
+        (Some(f), Some(s)) if s.call.len() > f.call.len() => Some(s),
+        (Some(f), _) => Some(f),
+        (None, s) => s,
+    }
+}
+
+/// Single entry of the CSV format
+struct Entry {
+    /// Line of the entry
+    line: usize,
+    /// Fields of the entry, including the type
+    fields: Vec<String>,
+}
+
+impl Entry {
+    /// Create a new entry.
+    ///
+    /// # Arguments
+    ///
+    /// - `line`: Line of the entry
+    /// - `fields`: Fields of the entry, including the type
+    ///
+    /// # Returns
+    ///
+    /// Entry
+    fn new(line: usize, fields: Vec<String>) -> Self {
+        Entry { line, fields }
+    }
+
+    /// Check the number of fields.
+    ///
+    /// # Arguments
+    ///
+    /// - `len`: Expected number of fields, including the type
+    ///
+    /// # Returns
+    ///
+    /// Nothing or an error
+    fn expect_len(&self, len: usize) -> Result<(), Error> {
+        if self.fields.len() == len {
+            Ok(())
+        } else {
+            Err(Error::InvalidFormat {
+                line: self.line,
+                reason: "wrong number of fields",
+            })
+        }
+    }
+
+    /// Get the value of an optional field.
+    ///
+    /// # Arguments
+    ///
+    /// - `idx`: Index of the field
+    /// - `name`: Name of the field, used for error reporting
+    ///
+    /// # Returns
+    ///
+    /// Converted value, `None` if empty or an error
+    fn optional<T: FromStr>(&self, idx: usize, name: &'static str) -> Result<Option<T>, Error> {
+        let value = self.fields[idx].as_str();
+        if value.is_empty() {
+            return Ok(None);
+        }
+
+        T::from_str(value)
+            .map(Some)
+            .map_err(|_| Error::InvalidValue {
+                line: self.line,
+                field: name,
+                value: String::from(value),
+            })
+    }
+
+    /// Get the value of a field that must be present.
+    ///
+    /// # Arguments
+    ///
+    /// - `idx`: Index of the field
+    /// - `name`: Name of the field, used for error reporting
+    ///
+    /// # Returns
+    ///
+    /// Converted value or an error
+    fn required<T: FromStr>(&self, idx: usize, name: &'static str) -> Result<T, Error> {
+        self.optional(idx, name)?.ok_or(Error::InvalidValue {
+            line: self.line,
+            field: name,
+            value: String::new(),
+        })
+    }
+
+    /// Get the value of an optional timestamp field.
+    ///
+    /// # Arguments
+    ///
+    /// - `idx`: Index of the field
+    /// - `name`: Name of the field, used for error reporting
+    ///
+    /// # Returns
+    ///
+    /// Timestamp, `None` if empty or an error
+    fn timestamp(&self, idx: usize, name: &'static str) -> Result<Option<DateTime<Utc>>, Error> {
+        Ok(self
+            .optional::<DateTime<chrono::FixedOffset>>(idx, name)?
+            .map(|d| d.into()))
+    }
+}
+
+/// Split a line of the CSV format into its fields.
+/// Fields may be enclosed in double quotes, two double quotes within a quoted field represent a single one.
+///
+/// # Arguments
+///
+/// - `raw`: Line to split
+/// - `line`: Line number, used for error reporting
+///
+/// # Returns
+///
+/// Trimmed fields or an error
+fn split_csv(raw: &str, line: usize) -> Result<Vec<String>, Error> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = raw.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                fields.push(field.trim().to_string());
+                field.clear();
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err(Error::InvalidFormat {
+            line,
+            reason: "unterminated quote",
+        });
+    }
+    fields.push(field.trim().to_string());
+
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::call::analyze_callsign;
+
+    const SAMPLE: &str = include_str!("../tests/data/cty_sample.xml");
+
+    fn base() -> ClubLogMap {
+        ClubLogMap::from(ClubLog::parse(SAMPLE).unwrap())
+    }
+
+    fn timestamp(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().into()
+    }
+
+    #[test]
+    fn overlay_csv() {
+        let overlay = Overlay::parse_csv(
+            "# Local corrections\n\
+             \n\
+             exception,DL1XYZ,CANADA,1,5,NA,-80.0,45.0,2020-01-01T00:00:00Z,\n\
+             prefix,VE9,\"FEDERAL REPUBLIC OF GERMANY\",230,14,EU,,,,\n\
+             invalid,DL2XYZ,,\n\
+             zone_exception,DL3XYZ,15,,\n",
+        )
+        .unwrap();
+        let data = ClubLogOverlay::new(base(), overlay);
+        let ts = timestamp("2023-01-01T00:00:00Z");
+
+        let exc = data.get_callsign_exception("DL1XYZ", &ts).unwrap();
+        assert_eq!(exc.record, OVERLAY_RECORD_OFFSET + 3);
+        assert_eq!(exc.adif, 1);
+        assert_eq!(exc.long, Some(-80.0));
+        assert_eq!(data.get_prefix("VE9", &ts).unwrap().adif, 230);
+        assert_eq!(data.get_prefix("VE", &ts).unwrap().adif, 1);
+        assert!(data.is_invalid_operation("DL2XYZ", &ts));
+        assert_eq!(data.get_zone_exception("DL3XYZ", &ts), Some(15));
+        assert_eq!(data.get_entity(230, &ts).unwrap().prefix, "DL");
+
+        assert_eq!(analyze_callsign(&data, "DL1XYZ", &ts).unwrap().adif, 1);
+        assert_eq!(analyze_callsign(&data, "VE9ABC", &ts).unwrap().adif, 230);
+    }
+
+    #[test]
+    fn overlay_xml() {
+        let overlay = Overlay::parse_xml(
+            r#"<clublog>
+                <exceptions>
+                    <exception record="100">
+                        <call>DL0XX</call>
+                        <entity>CANADA</entity>
+                        <adif>1</adif>
+                    </exception>
+                </exceptions>
+            </clublog>"#,
+        )
+        .unwrap();
+        let data = ClubLogOverlay::new(base(), overlay);
+        let ts = timestamp("2001-06-01T00:00:00Z");
+
+        // Exception of the overlay hides the invalid operation of the base data
+        assert!(data.base().is_invalid_operation("DL0XX", &ts));
+        assert!(!data.is_invalid_operation("DL0XX", &ts));
+        assert_eq!(analyze_callsign(&data, "DL0XX", &ts).unwrap().adif, 1);
+    }
+
+    #[test]
+    fn overlay_shadowing() {
+        let overlay = Overlay::parse_xml(
+            r#"<clublog>
+                <entities>
+                    <entity>
+                        <adif>230</adif>
+                        <name>GERMANY</name>
+                        <prefix>DL</prefix>
+                        <deleted>false</deleted>
+                    </entity>
+                </entities>
+                <exceptions>
+                    <exception record="100">
+                        <call>DL0ABC</call>
+                        <entity>GERMANY</entity>
+                        <adif>230</adif>
+                    </exception>
+                    <exception record="102">
+                        <call>DL0XX</call>
+                        <entity>GERMANY</entity>
+                        <adif>230</adif>
+                        <start>2001-01-01T00:00:00+00:00</start>
+                    </exception>
+                </exceptions>
+                <prefixes>
+                    <prefix record="101">
+                        <call>VE</call>
+                        <entity>GERMANY</entity>
+                        <adif>230</adif>
+                    </prefix>
+                </prefixes>
+                <zone_exceptions>
+                    <zone_exception record="103">
+                        <call>VE1ABC</call>
+                        <zone>3</zone>
+                        <start>2010-01-01T00:00:00+00:00</start>
+                    </zone_exception>
+                </zone_exceptions>
+            </clublog>"#,
+        )
+        .unwrap();
+        let data = ClubLogOverlay::new(base(), overlay);
+
+        // Records of the base data replaced by the overlay are not listed
+        let names: Vec<&str> = data
+            .search_entities("germany")
+            .map(|e| e.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["GERMANY"]);
+        assert_eq!(data.exceptions_by_adif(1).count(), 0);
+        let mut records: Vec<RecordId> = data.exceptions_by_adif(230).map(|e| e.record).collect();
+        records.sort();
+        assert_eq!(records, vec![100, 102]);
+        assert_eq!(data.prefixes_by_adif(1).count(), 0);
+        let mut records: Vec<RecordId> = data.prefixes_by_adif(230).map(|p| p.record).collect();
+        records.sort();
+        assert_eq!(records, vec![10, 101]);
+
+        // Zone exceptions and invalid operations agree with the point lookups
+        let records: Vec<RecordId> = data.iter_zone_exceptions().map(|z| z.record).collect();
+        assert_eq!(records, vec![103]);
+        let records: Vec<RecordId> = data
+            .zone_exception_history("VE1ABC")
+            .iter()
+            .map(|z| z.record)
+            .collect();
+        assert_eq!(records, vec![103]);
+        assert_eq!(
+            data.get_zone_exception("VE1ABC", &timestamp("2020-01-01T00:00:00Z")),
+            Some(3)
+        );
+
+        assert!(!data.is_invalid_operation("DL0XX", &timestamp("2001-06-01T00:00:00Z")));
+        assert_eq!(data.iter_invalid_operations().count(), 0);
+        assert!(data.invalid_operation_history("DL0XX").is_empty());
+    }
+
+    #[test]
+    fn overlay_errors() {
+        assert!(matches!(
+            Overlay::parse_csv("prefix,DL1"),
+            Err(Error::InvalidFormat { line: 1, .. })
+        ));
+        assert!(matches!(
+            Overlay::parse_csv("\nremove,DL1"),
+            Err(Error::InvalidFormat { line: 2, .. })
+        ));
+        assert!(matches!(
+            Overlay::parse_csv("zone_exception,DL1ABC,XX,,"),
+            Err(Error::InvalidValue {
+                line: 1,
+                field: "zone",
+                ..
+            })
+        ));
+        assert!(matches!(
+            Overlay::parse_csv("invalid,DL1ABC,2020-13-01,"),
+            Err(Error::InvalidValue {
+                line: 1,
+                field: "start",
+                ..
+            })
+        ));
+        assert!(matches!(
+            Overlay::parse_xml("<clublog><prefixes><prefix/></prefixes></clublog>"),
+            Err(Error::Xml(clublog::Error::MissingElement { .. }))
+        ));
+    }
+}
@@ -0,0 +1,754 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Event based parser for the ClubLog XML data.
+//!
+//! In contrast to a plain deserialization, the parser keeps track of the current element path, record and position within the document.
+//! This information is attached to every [error](Error) to simplify the analysis of broken data files.
+
+use crate::clublog::{
+    CallsignException, CallsignExceptions, ClubLog, Entities, Entity, Error, InvalidOperation,
+    InvalidOperations, Location, Prefix, Prefixes, RecordId, ZoneException, ZoneExceptions,
+};
+use chrono::{DateTime, Utc};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::io::BufRead;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Parse XML formatted ClubLog data from a reader.
+///
+/// # Arguments
+///
+/// - `reader`: Reader providing the XML data
+///
+/// # Returns
+///
+/// Parsed ClubLog data or an error
+pub(crate) fn parse<R: BufRead>(reader: R) -> Result<ClubLog, Error> {
+    Parser::new(reader, false).parse()
+}
+
+/// Parse XML formatted ClubLog data from a reader where all lists are optional.
+/// Missing lists are treated like empty ones.
+///
+/// # Arguments
+///
+/// - `reader`: Reader providing the XML data
+///
+/// # Returns
+///
+/// Parsed ClubLog data or an error
+pub(crate) fn parse_partial<R: BufRead>(reader: R) -> Result<ClubLog, Error> {
+    Parser::new(reader, true).parse()
+}
+
+/// Parse only the timestamp of XML formatted ClubLog data from a reader.
+/// Reading stops at the root element, the lists are not parsed.
+///
+/// # Arguments
+///
+/// - `reader`: Reader providing the XML data
+///
+/// # Returns
+///
+/// Timestamp of the ClubLog data or an error, also if the root element has no timestamp
+pub(crate) fn parse_date<R: BufRead>(reader: R) -> Result<DateTime<Utc>, Error> {
+    let mut parser = Parser::new(reader, false);
+    match parser.parse_root()? {
+        Some(date) => Ok(date),
+        None => Err(Error::MissingElement {
+            element: String::from("@date"),
+            location: parser.location(),
+        }),
+    }
+}
+
+/// Single child element of a record together with its location
+struct Field {
+    /// Name of the element
+    name: String,
+    /// Text content of the element
+    value: String,
+    /// Location of the element
+    location: Location,
+}
+
+/// Raw record like a single prefix or entity, collected before conversion into the actual type
+struct Record {
+    /// Value of the `record` attribute, if present
+    id: Option<RecordId>,
+    /// List of child elements
+    fields: Vec<Field>,
+    /// Location of the record element
+    location: Location,
+}
+
+impl Record {
+    /// Get the value of a field that must be present.
+    ///
+    /// # Arguments
+    ///
+    /// - `name`: Name of the field
+    ///
+    /// # Returns
+    ///
+    /// Converted value or an error
+    fn required<T: FieldValue>(&self, name: &str) -> Result<T, Error> {
+        self.optional(name)?.ok_or_else(|| Error::MissingElement {
+            element: String::from(name),
+            location: self.location.clone(),
+        })
+    }
+
+    /// Get the value of an optional field.
+    /// Empty elements are treated like missing ones.
+    ///
+    /// # Arguments
+    ///
+    /// - `name`: Name of the field
+    ///
+    /// # Returns
+    ///
+    /// Converted value, `None` if not present or an error
+    fn optional<T: FieldValue>(&self, name: &str) -> Result<Option<T>, Error> {
+        match self.fields.iter().find(|f| f.name == name) {
+            Some(field) if !field.value.is_empty() => {
+                T::convert(&field.value, &field.location).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Get the record identifier that must be present.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Record identifier or an error
+    fn id(&self) -> Result<RecordId, Error> {
+        self.id.ok_or_else(|| Error::MissingElement {
+            element: String::from("@record"),
+            location: self.location.clone(),
+        })
+    }
+}
+
+/// Conversion of the raw text of an element into a typed value
+trait FieldValue: Sized {
+    /// Convert raw text into the value.
+    ///
+    /// # Arguments
+    ///
+    /// - `value`: Raw text
+    /// - `location`: Location of the element, used for error reporting
+    ///
+    /// # Returns
+    ///
+    /// Converted value or an error
+    fn convert(value: &str, location: &Location) -> Result<Self, Error>;
+}
+
+impl FieldValue for String {
+    fn convert(value: &str, _location: &Location) -> Result<Self, Error> {
+        Ok(String::from(value))
+    }
+}
+
+impl FieldValue for bool {
+    fn convert(value: &str, location: &Location) -> Result<Self, Error> {
+        match value.trim() {
+            "true" | "1" => Ok(true),
+            "false" | "0" => Ok(false),
+            _ => Err(Error::InvalidBoolean {
+                value: String::from(value),
+                location: location.clone(),
+            }),
+        }
+    }
+}
+
+impl FieldValue for DateTime<Utc> {
+    fn convert(value: &str, location: &Location) -> Result<Self, Error> {
+        DateTime::parse_from_rfc3339(value.trim())
+            .map(|d| d.into())
+            .map_err(|e| Error::InvalidTimestamp {
+                value: String::from(value),
+                location: location.clone(),
+                source: e,
+            })
+    }
+}
+
+/// Implement [FieldValue] for numeric types based on their [FromStr] implementation
+macro_rules! impl_number_field_value {
+    ($($t:ty),*) => {
+        $(
+            impl FieldValue for $t {
+                fn convert(value: &str, location: &Location) -> Result<Self, Error> {
+                    <$t>::from_str(value.trim()).map_err(|_| Error::InvalidNumber {
+                        value: String::from(value),
+                        location: location.clone(),
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_number_field_value!(u8, u16, u32, f32);
+
+/// Streaming parser that keeps track of the position within the document
+struct Parser<R> {
+    /// XML reader
+    reader: Reader<R>,
+    /// Buffer for the events
+    buf: Vec<u8>,
+    /// Names of the currently open elements
+    path: Vec<String>,
+    /// Identifier of the record currently parsed
+    record: Option<RecordId>,
+    /// Number of lines read so far
+    line: usize,
+    /// Byte offset of the last event
+    event_offset: usize,
+    /// Line of the last event
+    event_line: usize,
+    /// Treat missing lists like empty ones
+    partial: bool,
+}
+
+impl<R: BufRead> Parser<R> {
+    /// Create a new parser.
+    ///
+    /// # Arguments
+    ///
+    /// - `reader`: Reader providing the XML data
+    /// - `partial`: Treat missing lists like empty ones
+    ///
+    /// # Returns
+    ///
+    /// Parser instance
+    fn new(reader: R, partial: bool) -> Self {
+        let mut reader = Reader::from_reader(reader);
+        reader.expand_empty_elements(true);
+
+        Parser {
+            reader,
+            buf: Vec::new(),
+            path: Vec::new(),
+            record: None,
+            line: 1,
+            event_offset: 0,
+            event_line: 1,
+            partial,
+        }
+    }
+
+    /// Location of the last event.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Current location
+    fn location(&self) -> Location {
+        Location {
+            path: self.path.join("/"),
+            record: self.record,
+            offset: self.event_offset,
+            line: self.event_line,
+        }
+    }
+
+    /// Read the next event from the document and update the position information.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Next event or an error
+    fn next_event(&mut self) -> Result<Event<'static>, Error> {
+        self.event_offset = self.reader.buffer_position();
+        self.event_line = self.line;
+
+        let result = self
+            .reader
+            .read_event_into(&mut self.buf)
+            .map(|e| e.into_owned());
+        self.buf.clear();
+
+        let event = match result {
+            Ok(event) => event,
+            Err(quick_xml::Error::Io(e)) => {
+                return Err(Error::Io(
+                    Arc::try_unwrap(e)
+                        .unwrap_or_else(|e| std::io::Error::new(e.kind(), e.to_string())),
+                ))
+            }
+            Err(e) => return Err(self.xml_error(e)),
+        };
+
+        self.line += event.iter().filter(|c| **c == b'\n').count();
+
+        Ok(event)
+    }
+
+    /// Create a XML syntax error for the current location.
+    ///
+    /// # Arguments
+    ///
+    /// - `source`: Error reported by the XML reader
+    ///
+    /// # Returns
+    ///
+    /// Error
+    fn xml_error(&self, source: quick_xml::Error) -> Error {
+        Error::Xml {
+            location: self.location(),
+            source,
+        }
+    }
+
+    /// Create an error for an unexpected end of the document.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Error
+    fn eof_error(&self) -> Error {
+        self.xml_error(quick_xml::Error::UnexpectedEof(self.path.join("/")))
+    }
+
+    /// Read the value of an attribute of an element.
+    ///
+    /// # Arguments
+    ///
+    /// - `element`: Element to read the attribute from
+    /// - `name`: Name of the attribute
+    ///
+    /// # Returns
+    ///
+    /// Value of the attribute, `None` if not present or an error
+    fn attribute(&self, element: &BytesStart, name: &str) -> Result<Option<String>, Error> {
+        let attr = element
+            .try_get_attribute(name)
+            .map_err(|e| self.xml_error(e))?;
+
+        match attr {
+            Some(attr) => Ok(Some(
+                attr.unescape_value()
+                    .map_err(|e| self.xml_error(e))?
+                    .into_owned(),
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Skip the remaining content of an element whose start was already read.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Nothing or an error
+    fn skip_element(&mut self) -> Result<(), Error> {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.next_event()? {
+                Event::Start(_) => depth += 1,
+                Event::End(_) => depth -= 1,
+                Event::Eof => return Err(self.eof_error()),
+                _ => (),
+            }
+        }
+        self.path.pop();
+
+        Ok(())
+    }
+
+    /// Read the next child element of the current element.
+    /// If a child element is found, its name gets appended to the path.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Start of the child element, `None` if the end of the current element was reached or an error
+    fn next_child(&mut self) -> Result<Option<BytesStart<'static>>, Error> {
+        loop {
+            match self.next_event()? {
+                Event::Start(e) => {
+                    self.path
+                        .push(String::from_utf8_lossy(e.local_name().as_ref()).into_owned());
+                    return Ok(Some(e));
+                }
+                Event::End(_) => return Ok(None),
+                Event::Eof => return Err(self.eof_error()),
+                _ => (),
+            }
+        }
+    }
+
+    /// Parse the complete document.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Parsed ClubLog data or an error
+    fn parse(mut self) -> Result<ClubLog, Error> {
+        let date = self.parse_root()?.unwrap_or_default();
+        let root_location = self.location();
+
+        let mut entities = None;
+        let mut exceptions = None;
+        let mut prefixes = None;
+        let mut invalid_operations = None;
+        let mut zone_exceptions = None;
+
+        while self.next_child()?.is_some() {
+            match self.path.last().unwrap().as_str() {
+                "entities" => {
+                    entities = Some(self.parse_list("entity", to_entity)?);
+                }
+                "exceptions" => {
+                    exceptions = Some(self.parse_list("exception", to_callsign_exception)?);
+                }
+                "prefixes" => {
+                    prefixes = Some(self.parse_list("prefix", to_prefix)?);
+                }
+                "invalid_operations" => {
+                    invalid_operations = Some(self.parse_list("invalid", to_invalid_operation)?);
+                }
+                "zone_exceptions" => {
+                    zone_exceptions = Some(self.parse_list("zone_exception", to_zone_exception)?);
+                }
+                _ => self.skip_element()?,
+            }
+        }
+
+        Ok(ClubLog {
+            date,
+            entities: Entities {
+                list: self.require_list(entities, "entities", &root_location)?,
+            },
+            exceptions: CallsignExceptions {
+                list: self.require_list(exceptions, "exceptions", &root_location)?,
+            },
+            prefixes: Prefixes {
+                list: self.require_list(prefixes, "prefixes", &root_location)?,
+            },
+            invalid_operations: InvalidOperations {
+                list: self.require_list(
+                    invalid_operations,
+                    "invalid_operations",
+                    &root_location,
+                )?,
+            },
+            zone_exceptions: ZoneExceptions {
+                list: self.require_list(zone_exceptions, "zone_exceptions", &root_location)?,
+            },
+        })
+    }
+
+    /// Search for the root element and parse its timestamp.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Timestamp of the ClubLog data, if present, or an error
+    fn parse_root(&mut self) -> Result<Option<DateTime<Utc>>, Error> {
+        // Search for the root element
+        let root = loop {
+            match self.next_event()? {
+                Event::Start(e) => break e,
+                Event::Eof => {
+                    return Err(Error::MissingElement {
+                        element: String::from("clublog"),
+                        location: self.location(),
+                    })
+                }
+                _ => (),
+            }
+        };
+
+        if root.local_name().as_ref() != b"clublog" {
+            return Err(Error::MissingElement {
+                element: String::from("clublog"),
+                location: self.location(),
+            });
+        }
+        self.path.push(String::from("clublog"));
+        let root_location = self.location();
+
+        self.attribute(&root, "date")?
+            .map(|date| FieldValue::convert(&date, &root_location))
+            .transpose()
+    }
+
+    /// Check the presence of a list that was parsed.
+    ///
+    /// # Arguments
+    ///
+    /// - `list`: Parsed list, `None` if the element was not found
+    /// - `element`: Name of the list element
+    /// - `location`: Location of the root element, used for error reporting
+    ///
+    /// # Returns
+    ///
+    /// List, empty if not present in the partial mode, or an error
+    fn require_list<T>(
+        &self,
+        list: Option<Vec<T>>,
+        element: &str,
+        location: &Location,
+    ) -> Result<Vec<T>, Error> {
+        match list {
+            Some(list) => Ok(list),
+            None if self.partial => Ok(Vec::new()),
+            None => Err(Error::MissingElement {
+                element: String::from(element),
+                location: location.clone(),
+            }),
+        }
+    }
+
+    /// Parse a list of records like the list of prefixes.
+    /// The start element of the list was already read and is the last element of the path.
+    ///
+    /// # Arguments
+    ///
+    /// - `item`: Name of the elements within the list
+    /// - `convert`: Function to convert a raw record into the actual type
+    ///
+    /// # Returns
+    ///
+    /// List of records or an error
+    fn parse_list<T>(
+        &mut self,
+        item: &str,
+        convert: fn(&Record) -> Result<T, Error>,
+    ) -> Result<Vec<T>, Error> {
+        let mut list = Vec::new();
+
+        while let Some(element) = self.next_child()? {
+            if self.path.last().unwrap() == item {
+                let record = self.parse_record(&element)?;
+                list.push(convert(&record)?);
+                self.record = None;
+                self.path.pop();
+            } else {
+                self.skip_element()?;
+            }
+        }
+
+        self.path.pop();
+
+        Ok(list)
+    }
+
+    /// Parse the fields of a single record.
+    /// The start element of the record was already read and is the last element of the path.
+    ///
+    /// # Arguments
+    ///
+    /// - `element`: Start element of the record
+    ///
+    /// # Returns
+    ///
+    /// Raw record or an error
+    fn parse_record(&mut self, element: &BytesStart) -> Result<Record, Error> {
+        let location = self.location();
+        let id = match self.attribute(element, "record")? {
+            Some(id) => Some(FieldValue::convert(&id, &location)?),
+            None => None,
+        };
+        self.record = id;
+        let location = self.location();
+
+        let mut fields = Vec::new();
+
+        while self.next_child()?.is_some() {
+            let location = self.location();
+            let value = self.parse_text()?;
+            let name = self.path.pop().unwrap();
+
+            fields.push(Field {
+                name,
+                value,
+                location,
+            });
+        }
+
+        Ok(Record {
+            id,
+            fields,
+            location,
+        })
+    }
+
+    /// Read the text content of an element whose start was already read.
+    /// Nested elements are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Text content or an error
+    fn parse_text(&mut self) -> Result<String, Error> {
+        let mut value = String::new();
+
+        loop {
+            match self.next_event()? {
+                Event::Text(t) => value.push_str(&t.unescape().map_err(|e| self.xml_error(e))?),
+                Event::CData(c) => value.push_str(&String::from_utf8_lossy(&c)),
+                Event::Start(e) => {
+                    self.path
+                        .push(String::from_utf8_lossy(e.local_name().as_ref()).into_owned());
+                    self.skip_element()?;
+                }
+                Event::End(_) => break,
+                Event::Eof => return Err(self.eof_error()),
+                _ => (),
+            }
+        }
+
+        Ok(value)
+    }
+}
+
+/// Convert a raw record into an entity.
+///
+/// # Arguments
+///
+/// - `record`: Raw record
+///
+/// # Returns
+///
+/// Entity or an error
+fn to_entity(record: &Record) -> Result<Entity, Error> {
+    Ok(Entity {
+        adif: record.required("adif")?,
+        name: record.required("name")?,
+        prefix: record.required("prefix")?,
+        deleted: record.required("deleted")?,
+        cqz: record.optional("cqz")?,
+        cont: record.optional("cont")?,
+        long: record.optional("long")?,
+        lat: record.optional("lat")?,
+        start: record.optional("start")?,
+        end: record.optional("end")?,
+        whitelist: record.optional("whitelist")?,
+        whitelist_start: record.optional("whitelist_start")?,
+        whitelist_end: record.optional("whitelist_end")?,
+    })
+}
+
+/// Convert a raw record into a callsign exception.
+///
+/// # Arguments
+///
+/// - `record`: Raw record
+///
+/// # Returns
+///
+/// Callsign exception or an error
+fn to_callsign_exception(record: &Record) -> Result<CallsignException, Error> {
+    Ok(CallsignException {
+        record: record.id()?,
+        call: record.required("call")?,
+        entity: record.required("entity")?,
+        adif: record.required("adif")?,
+        cqz: record.optional("cqz")?,
+        cont: record.optional("cont")?,
+        long: record.optional("long")?,
+        lat: record.optional("lat")?,
+        start: record.optional("start")?,
+        end: record.optional("end")?,
+    })
+}
+
+/// Convert a raw record into a prefix.
+///
+/// # Arguments
+///
+/// - `record`: Raw record
+///
+/// # Returns
+///
+/// Prefix or an error
+fn to_prefix(record: &Record) -> Result<Prefix, Error> {
+    Ok(Prefix {
+        record: record.id()?,
+        call: record.required("call")?,
+        entity: record.required("entity")?,
+        adif: record.required("adif")?,
+        cqz: record.optional("cqz")?,
+        cont: record.optional("cont")?,
+        long: record.optional("long")?,
+        lat: record.optional("lat")?,
+        start: record.optional("start")?,
+        end: record.optional("end")?,
+    })
+}
+
+/// Convert a raw record into an invalid operation.
+///
+/// # Arguments
+///
+/// - `record`: Raw record
+///
+/// # Returns
+///
+/// Invalid operation or an error
+fn to_invalid_operation(record: &Record) -> Result<InvalidOperation, Error> {
+    Ok(InvalidOperation {
+        record: record.id()?,
+        call: record.required("call")?,
+        start: record.optional("start")?,
+        end: record.optional("end")?,
+    })
+}
+
+/// Convert a raw record into a CQ zone exception.
+///
+/// # Arguments
+///
+/// - `record`: Raw record
+///
+/// # Returns
+///
+/// CQ zone exception or an error
+fn to_zone_exception(record: &Record) -> Result<ZoneException, Error> {
+    Ok(ZoneException {
+        record: record.id()?,
+        call: record.required("call")?,
+        zone: record.required("zone")?,
+        start: record.optional("start")?,
+        end: record.optional("end")?,
+    })
+}
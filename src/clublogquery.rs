@@ -4,8 +4,14 @@
 
 //! Trait definition on how to access ClubLog data.
 
-use crate::clublog::{Adif, CallsignException, CqZone, Entity, Prefix};
+use crate::clublog::{
+    Adif, CallsignException, CqZone, Entity, InvalidOperation, Prefix, RecordId, ZoneException,
+};
 use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+/// Iterator over entries of the ClubLog data, returned by the enumeration and search methods of [ClubLogQuery]
+pub type QueryIter<'a, T> = Box<dyn Iterator<Item = &'a T> + 'a>;
 
 /// Definitions on how to access ClubLog data
 pub trait ClubLogQuery {
@@ -33,6 +39,51 @@ pub trait ClubLogQuery {
     /// Prefix information, if present
     fn get_prefix(&self, prefix: &str, timestamp: &DateTime<Utc>) -> Option<&Prefix>;
 
+    /// Get prefix information of the longest prefix the callsign begins with.
+    ///
+    /// The default implementation queries [get_prefix](ClubLogQuery::get_prefix) for the callsign shortened char by char from the back.
+    ///
+    /// # Arguments
+    ///
+    /// - `call`: Callsign or part of it, like `UA9ABC`
+    /// - `timestamp`: Timestamp to use for the check
+    ///
+    /// # Returns
+    ///
+    /// Prefix information of the longest matching prefix, like `UA9` instead of `U` for `UA9ABC`, if present
+    fn longest_prefix_match(&self, call: &str, timestamp: &DateTime<Utc>) -> Option<&Prefix> {
+        (1..=call.len())
+            .rev()
+            .filter(|len| call.is_char_boundary(*len))
+            .find_map(|len| self.get_prefix(&call[..len], timestamp))
+    }
+
+    /// Get prefix information of the longest special prefix `<prefix>/<appendix>`, where the callsign begins with `<prefix>`.
+    /// Special prefixes like `SV/A` only apply, if the callsign carries the appendix, like `SV1ABC/A`.
+    ///
+    /// The default implementation queries [get_prefix](ClubLogQuery::get_prefix) for the callsign shortened char by char from the back.
+    ///
+    /// # Arguments
+    ///
+    /// - `call`: Callsign or part of it without the appendix, like `SV1ABC`
+    /// - `appendix`: Appendix of the callsign, like `A`
+    /// - `timestamp`: Timestamp to use for the check
+    ///
+    /// # Returns
+    ///
+    /// Prefix information of the longest matching special prefix, if present
+    fn longest_prefix_match_with_appendix(
+        &self,
+        call: &str,
+        appendix: &str,
+        timestamp: &DateTime<Utc>,
+    ) -> Option<&Prefix> {
+        (1..=call.len())
+            .rev()
+            .filter(|len| call.is_char_boundary(*len))
+            .find_map(|len| self.get_prefix(&format!("{}/{}", &call[..len], appendix), timestamp))
+    }
+
     /// Get callsign exception information by callsign.
     ///
     /// # Arguments
@@ -72,6 +123,398 @@ pub trait ClubLogQuery {
     ///
     /// True if the operation is invalid, false otherwise
     fn is_invalid_operation(&self, callsign: &str, timestamp: &DateTime<Utc>) -> bool;
+
+    /// List all entities, independent of their time window.
+    /// The order of the entities is unspecified.
+    ///
+    /// The enumeration and search methods are built on top of this method.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Iterator over the entities
+    fn iter_entities(&self) -> QueryIter<'_, Entity>;
+
+    /// List all prefixes, independent of their time window.
+    /// The order of the prefixes is unspecified.
+    ///
+    /// [prefixes_by_adif](ClubLogQuery::prefixes_by_adif) and [prefix_history](ClubLogQuery::prefix_history) are built on top of this method.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Iterator over the prefixes
+    fn iter_prefixes(&self) -> QueryIter<'_, Prefix>;
+
+    /// List all callsign exceptions, independent of their time window.
+    /// The order of the callsign exceptions is unspecified.
+    ///
+    /// [exceptions_by_adif](ClubLogQuery::exceptions_by_adif) and [callsign_exception_history](ClubLogQuery::callsign_exception_history) are built on top of this method.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Iterator over the callsign exceptions
+    fn iter_callsign_exceptions(&self) -> QueryIter<'_, CallsignException>;
+
+    /// List all CQ zone exceptions, independent of their time window.
+    /// The order of the CQ zone exceptions is unspecified.
+    ///
+    /// [zone_exception_history](ClubLogQuery::zone_exception_history) is built on top of this method.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Iterator over the CQ zone exceptions
+    fn iter_zone_exceptions(&self) -> QueryIter<'_, ZoneException>;
+
+    /// List all invalid operations, independent of their time window.
+    /// The order of the invalid operations is unspecified.
+    ///
+    /// [invalid_operation_history](ClubLogQuery::invalid_operation_history) is built on top of this method.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Iterator over the invalid operations
+    fn iter_invalid_operations(&self) -> QueryIter<'_, InvalidOperation>;
+
+    /// Check whether the enumeration methods list all records, including the ones outside of the queried time windows.
+    /// Only then the history methods reveal when the answers of the other queries change,
+    /// which is required by the [CallsignAnalyzer](crate::callanalyzer::CallsignAnalyzer) to cache results.
+    ///
+    /// The default implementation returns false, implementations listing all their records should override it.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// True if all records are listed, false otherwise
+    fn supports_history(&self) -> bool {
+        false
+    }
+
+    /// Get entity information by the main prefix of the entity.
+    ///
+    /// The default implementation searches the entities listed by [iter_entities](ClubLogQuery::iter_entities).
+    ///
+    /// # Arguments
+    ///
+    /// - `prefix`: Main prefix of the entity, like `DL`
+    /// - `timestamp`: Timestamp to use for the check
+    ///
+    /// # Returns
+    ///
+    /// Entity information, if present
+    fn get_entity_by_prefix(&self, prefix: &str, timestamp: &DateTime<Utc>) -> Option<&Entity> {
+        self.iter_entities()
+            .find(|e| e.prefix == prefix && is_in_time_window(timestamp, e.start, e.end))
+    }
+
+    /// List all entities valid at the given point in time.
+    /// The order of the entities is unspecified.
+    ///
+    /// The default implementation filters the entities listed by [iter_entities](ClubLogQuery::iter_entities).
+    ///
+    /// # Arguments
+    ///
+    /// - `timestamp`: Timestamp to use for the check
+    ///
+    /// # Returns
+    ///
+    /// Iterator over the entities
+    fn entities(&self, timestamp: &DateTime<Utc>) -> QueryIter<'_, Entity> {
+        let timestamp = *timestamp;
+        Box::new(
+            self.iter_entities()
+                .filter(move |e| is_in_time_window(&timestamp, e.start, e.end)),
+        )
+    }
+
+    /// Search entities by their name.
+    /// The search is case-insensitive and matches any part of the name, like `germany` for `FEDERAL REPUBLIC OF GERMANY`.
+    /// Entities of all points in time are considered, including deleted ones.
+    /// The order of the entities is unspecified.
+    ///
+    /// The default implementation filters the entities listed by [iter_entities](ClubLogQuery::iter_entities).
+    ///
+    /// # Arguments
+    ///
+    /// - `name`: Part of the name to search for
+    ///
+    /// # Returns
+    ///
+    /// Iterator over the matching entities
+    fn search_entities<'a>(&'a self, name: &str) -> QueryIter<'a, Entity> {
+        let name = name.to_lowercase();
+        Box::new(
+            self.iter_entities()
+                .filter(move |e| entity_name_matches(e, &name)),
+        )
+    }
+
+    /// List all prefixes referencing an entity, independent of their time window.
+    /// The order of the prefixes is unspecified.
+    ///
+    /// The default implementation filters the prefixes listed by [iter_prefixes](ClubLogQuery::iter_prefixes).
+    ///
+    /// # Arguments
+    ///
+    /// - `adif`: ADIF identifier of the entity
+    ///
+    /// # Returns
+    ///
+    /// Iterator over the prefixes
+    fn prefixes_by_adif(&self, adif: Adif) -> QueryIter<'_, Prefix> {
+        Box::new(self.iter_prefixes().filter(move |p| p.adif == adif))
+    }
+
+    /// List all callsign exceptions referencing an entity, independent of their time window.
+    /// The order of the callsign exceptions is unspecified.
+    ///
+    /// The default implementation filters the callsign exceptions listed by [iter_callsign_exceptions](ClubLogQuery::iter_callsign_exceptions).
+    ///
+    /// # Arguments
+    ///
+    /// - `adif`: ADIF identifier of the entity
+    ///
+    /// # Returns
+    ///
+    /// Iterator over the callsign exceptions
+    fn exceptions_by_adif(&self, adif: Adif) -> QueryIter<'_, CallsignException> {
+        Box::new(
+            self.iter_callsign_exceptions()
+                .filter(move |e| e.adif == adif),
+        )
+    }
+
+    /// Get all prefix records of a prefix, independent of their time window.
+    ///
+    /// The default implementation filters the prefixes listed by [iter_prefixes](ClubLogQuery::iter_prefixes).
+    ///
+    /// # Arguments
+    ///
+    /// - `prefix`: Callsigns prefix, like `DL`
+    ///
+    /// # Returns
+    ///
+    /// Prefix records sorted chronologically by their start, records without start first
+    fn prefix_history(&self, prefix: &str) -> Vec<&Prefix> {
+        chronological(
+            self.iter_prefixes().filter(|p| p.call == prefix).collect(),
+            |p| (p.start, p.record),
+        )
+    }
+
+    /// Get all callsign exceptions of a callsign, independent of their time window.
+    ///
+    /// The default implementation filters the callsign exceptions listed by [iter_callsign_exceptions](ClubLogQuery::iter_callsign_exceptions).
+    ///
+    /// # Arguments
+    ///
+    /// - `callsign`: Complete callsign
+    ///
+    /// # Returns
+    ///
+    /// Callsign exceptions sorted chronologically by their start, records without start first
+    fn callsign_exception_history(&self, callsign: &str) -> Vec<&CallsignException> {
+        chronological(
+            self.iter_callsign_exceptions()
+                .filter(|e| e.call == callsign)
+                .collect(),
+            |e| (e.start, e.record),
+        )
+    }
+
+    /// Get all CQ zone exceptions of a callsign, independent of their time window.
+    ///
+    /// The default implementation filters the CQ zone exceptions listed by [iter_zone_exceptions](ClubLogQuery::iter_zone_exceptions).
+    ///
+    /// # Arguments
+    ///
+    /// - `callsign`: Complete callsign
+    ///
+    /// # Returns
+    ///
+    /// CQ zone exceptions sorted chronologically by their start, records without start first
+    fn zone_exception_history(&self, callsign: &str) -> Vec<&ZoneException> {
+        chronological(
+            self.iter_zone_exceptions()
+                .filter(|z| z.call == callsign)
+                .collect(),
+            |z| (z.start, z.record),
+        )
+    }
+
+    /// Get all invalid operations of a callsign, independent of their time window.
+    ///
+    /// The default implementation filters the invalid operations listed by [iter_invalid_operations](ClubLogQuery::iter_invalid_operations).
+    ///
+    /// # Arguments
+    ///
+    /// - `callsign`: Complete callsign
+    ///
+    /// # Returns
+    ///
+    /// Invalid operations sorted chronologically by their start, records without start first
+    fn invalid_operation_history(&self, callsign: &str) -> Vec<&InvalidOperation> {
+        chronological(
+            self.iter_invalid_operations()
+                .filter(|o| o.call == callsign)
+                .collect(),
+            |o| (o.start, o.record),
+        )
+    }
+}
+
+/// Sort records chronologically by their start timestamp.
+/// Records without start timestamp come first, records with the same start are sorted by their record identifier.
+///
+/// # Arguments
+///
+/// - `records`: Records to sort
+/// - `key`: Function to get the start timestamp and the record identifier of a record
+///
+/// # Returns
+///
+/// Sorted records
+pub(crate) fn chronological<T>(
+    mut records: Vec<&T>,
+    key: fn(&T) -> (Option<DateTime<Utc>>, RecordId),
+) -> Vec<&T> {
+    records.sort_by_key(|r| key(r));
+    records
+}
+
+/// Check whether the name of an entity contains a search term, ignoring the case.
+///
+/// # Arguments
+///
+/// - `entity`: Entity to check
+/// - `name`: Search term in lowercase
+///
+/// # Returns
+///
+/// True if the name contains the search term, false otherwise
+pub(crate) fn entity_name_matches(entity: &Entity, name: &str) -> bool {
+    entity.name.to_lowercase().contains(name)
+}
+
+/// Shared data, like a snapshot of a [ClubLogHandle](crate::clubloghandle::ClubLogHandle), is queried directly
+impl<Q: ClubLogQuery + ?Sized> ClubLogQuery for Arc<Q> {
+    fn get_entity(&self, adif: Adif, timestamp: &DateTime<Utc>) -> Option<&Entity> {
+        (**self).get_entity(adif, timestamp)
+    }
+
+    fn get_prefix(&self, prefix: &str, timestamp: &DateTime<Utc>) -> Option<&Prefix> {
+        (**self).get_prefix(prefix, timestamp)
+    }
+
+    fn longest_prefix_match(&self, call: &str, timestamp: &DateTime<Utc>) -> Option<&Prefix> {
+        (**self).longest_prefix_match(call, timestamp)
+    }
+
+    fn longest_prefix_match_with_appendix(
+        &self,
+        call: &str,
+        appendix: &str,
+        timestamp: &DateTime<Utc>,
+    ) -> Option<&Prefix> {
+        (**self).longest_prefix_match_with_appendix(call, appendix, timestamp)
+    }
+
+    fn get_callsign_exception(
+        &self,
+        callsign: &str,
+        timestamp: &DateTime<Utc>,
+    ) -> Option<&CallsignException> {
+        (**self).get_callsign_exception(callsign, timestamp)
+    }
+
+    fn get_zone_exception(&self, callsign: &str, timestamp: &DateTime<Utc>) -> Option<CqZone> {
+        (**self).get_zone_exception(callsign, timestamp)
+    }
+
+    fn is_invalid_operation(&self, callsign: &str, timestamp: &DateTime<Utc>) -> bool {
+        (**self).is_invalid_operation(callsign, timestamp)
+    }
+
+    fn iter_entities(&self) -> QueryIter<'_, Entity> {
+        (**self).iter_entities()
+    }
+
+    fn iter_prefixes(&self) -> QueryIter<'_, Prefix> {
+        (**self).iter_prefixes()
+    }
+
+    fn iter_callsign_exceptions(&self) -> QueryIter<'_, CallsignException> {
+        (**self).iter_callsign_exceptions()
+    }
+
+    fn iter_zone_exceptions(&self) -> QueryIter<'_, ZoneException> {
+        (**self).iter_zone_exceptions()
+    }
+
+    fn iter_invalid_operations(&self) -> QueryIter<'_, InvalidOperation> {
+        (**self).iter_invalid_operations()
+    }
+
+    fn supports_history(&self) -> bool {
+        (**self).supports_history()
+    }
+
+    fn get_entity_by_prefix(&self, prefix: &str, timestamp: &DateTime<Utc>) -> Option<&Entity> {
+        (**self).get_entity_by_prefix(prefix, timestamp)
+    }
+
+    fn entities(&self, timestamp: &DateTime<Utc>) -> QueryIter<'_, Entity> {
+        (**self).entities(timestamp)
+    }
+
+    fn search_entities<'a>(&'a self, name: &str) -> QueryIter<'a, Entity> {
+        (**self).search_entities(name)
+    }
+
+    fn prefixes_by_adif(&self, adif: Adif) -> QueryIter<'_, Prefix> {
+        (**self).prefixes_by_adif(adif)
+    }
+
+    fn exceptions_by_adif(&self, adif: Adif) -> QueryIter<'_, CallsignException> {
+        (**self).exceptions_by_adif(adif)
+    }
+
+    fn prefix_history(&self, prefix: &str) -> Vec<&Prefix> {
+        (**self).prefix_history(prefix)
+    }
+
+    fn callsign_exception_history(&self, callsign: &str) -> Vec<&CallsignException> {
+        (**self).callsign_exception_history(callsign)
+    }
+
+    fn zone_exception_history(&self, callsign: &str) -> Vec<&ZoneException> {
+        (**self).zone_exception_history(callsign)
+    }
+
+    fn invalid_operation_history(&self, callsign: &str) -> Vec<&InvalidOperation> {
+        (**self).invalid_operation_history(callsign)
+    }
 }
 
 /// Check whether a timestamp is within an optional start and end time range.
@@ -97,3 +540,27 @@ pub fn is_in_time_window(
         (None, None) => true,
     }
 }
+
+/// Check whether two optional time windows overlap.
+/// Missing start or end timestamps are interpreted as unlimited.
+///
+/// # Arguments
+///
+/// - `first`: Start and end timestamp of the first time window
+/// - `second`: Start and end timestamp of the second time window
+///
+/// # Returns
+///
+/// True if there is at least one point in time within both time windows, false otherwise
+pub fn time_windows_overlap(
+    first: (Option<DateTime<Utc>>, Option<DateTime<Utc>>),
+    second: (Option<DateTime<Utc>>, Option<DateTime<Utc>>),
+) -> bool {
+    let starts_before_end =
+        |start: Option<DateTime<Utc>>, end: Option<DateTime<Utc>>| match (start, end) {
+            (Some(start), Some(end)) => start <= end,
+            _ => true,
+        };
+
+    starts_before_end(first.0, second.1) && starts_before_end(second.0, first.1)
+}
@@ -0,0 +1,573 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Compact binary snapshot format of the ClubLog data.
+//!
+//! Loading a snapshot avoids parsing the XML file on every start of an application.
+//! A snapshot begins with a header that consists of the [magic bytes](SNAPSHOT_MAGIC), the [format version](SNAPSHOT_VERSION) and the timestamp of the ClubLog data.
+//! The header is followed by the index of the [ClubLogMap](crate::clublogmap::ClubLogMap) as it was built:
+//! the entities, the callsign exceptions grouped by callsign, the nodes of the prefix trie, the invalid operations grouped by callsign and the CQ zone exceptions grouped by callsign.
+//! Therefore loading a snapshot does not need to rebuild the index.
+//! Entities are ordered by their ADIF identifier and groups by the record identifier of their first record, so the same data always results in the same snapshot.
+//! All numbers are stored in little endian byte order, strings and lists are prefixed by their length.
+//!
+//! Snapshots are created with [ClubLogMap::write_snapshot](crate::clublogmap::ClubLogMap::write_snapshot) and loaded with [ClubLogMap::from_snapshot](crate::clublogmap::ClubLogMap::from_snapshot).
+//! The loader accepts any byte slice, e.g. the content of a file read into memory or of a mapped file.
+//! The records are copied out of the slice into owned values while loading, the slice is not used in place.
+//! Use [snapshot_date] to check whether a snapshot is stale without loading it completely.
+
+use crate::clublog::{
+    CallsignException, Entity, InvalidOperation, Prefix, RecordId, ZoneException,
+};
+use chrono::{DateTime, Utc};
+use std::io::Write;
+use thiserror::Error;
+
+/// Magic bytes at the beginning of each snapshot
+pub const SNAPSHOT_MAGIC: [u8; 8] = *b"HAMCALL\0";
+
+/// Version of the snapshot format
+pub const SNAPSHOT_VERSION: u16 = 1;
+
+/// Possible errors while reading or writing a snapshot
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Reading or writing the snapshot failed
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Data does not begin with the magic bytes of a snapshot
+    #[error("Data is not a snapshot")]
+    InvalidMagic,
+
+    /// Snapshot was created with a different version of the format
+    #[error("Unsupported snapshot version {0}, expected version {SNAPSHOT_VERSION}")]
+    UnsupportedVersion(u16),
+
+    /// Snapshot is truncated or contains invalid values
+    #[error("Snapshot is corrupted")]
+    Corrupted,
+
+    /// Length or index exceeds the range of the snapshot format while writing
+    #[error("Value {0} exceeds the range of the snapshot format")]
+    Overflow(usize),
+}
+
+/// Read the timestamp of the ClubLog data a snapshot was created from.
+/// Only the header of the snapshot is read.
+///
+/// # Arguments
+///
+/// - `data`: Content of the snapshot
+///
+/// # Returns
+///
+/// Timestamp of the ClubLog data or an error
+pub fn snapshot_date(data: &[u8]) -> Result<DateTime<Utc>, Error> {
+    SnapshotReader::new(data)?.date()
+}
+
+/// Writer for the snapshot format
+pub(crate) struct SnapshotWriter<W> {
+    /// Underlying writer
+    writer: W,
+}
+
+impl<W: Write> SnapshotWriter<W> {
+    /// Create a new snapshot writer and write the header.
+    ///
+    /// # Arguments
+    ///
+    /// - `writer`: Underlying writer
+    /// - `date`: Timestamp of the ClubLog data
+    ///
+    /// # Returns
+    ///
+    /// Snapshot writer or an error
+    pub(crate) fn new(writer: W, date: &DateTime<Utc>) -> Result<Self, Error> {
+        let mut snapshot = SnapshotWriter { writer };
+        snapshot.writer.write_all(&SNAPSHOT_MAGIC)?;
+        snapshot.writer.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+        snapshot.datetime(date)?;
+        Ok(snapshot)
+    }
+
+    /// Write the length of a list.
+    ///
+    /// # Arguments
+    ///
+    /// - `len`: Length of the list
+    ///
+    /// # Returns
+    ///
+    /// Nothing or an error
+    pub(crate) fn len(&mut self, len: usize) -> Result<(), Error> {
+        let len = u32::try_from(len).map_err(|_| Error::Overflow(len))?;
+        self.writer.write_all(&len.to_le_bytes())?;
+        Ok(())
+    }
+
+    /// Write a child of a trie node.
+    ///
+    /// # Arguments
+    ///
+    /// - `byte`: Byte of the key leading to the child
+    /// - `index`: Index of the child node
+    ///
+    /// # Returns
+    ///
+    /// Nothing or an error
+    pub(crate) fn child(&mut self, byte: u8, index: usize) -> Result<(), Error> {
+        let index = u32::try_from(index).map_err(|_| Error::Overflow(index))?;
+        self.writer.write_all(&[byte])?;
+        self.writer.write_all(&index.to_le_bytes())?;
+        Ok(())
+    }
+
+    /// Write an entity.
+    ///
+    /// # Arguments
+    ///
+    /// - `entity`: Entity to write
+    ///
+    /// # Returns
+    ///
+    /// Nothing or an error
+    pub(crate) fn entity(&mut self, entity: &Entity) -> Result<(), Error> {
+        self.writer.write_all(&entity.adif.to_le_bytes())?;
+        self.string(&entity.name)?;
+        self.string(&entity.prefix)?;
+        self.bool(entity.deleted)?;
+        self.location(&entity.cqz, &entity.cont, &entity.long, &entity.lat)?;
+        self.datetime_opt(&entity.start)?;
+        self.datetime_opt(&entity.end)?;
+        self.option(&entity.whitelist, |s, v| s.bool(*v))?;
+        self.datetime_opt(&entity.whitelist_start)?;
+        self.datetime_opt(&entity.whitelist_end)
+    }
+
+    /// Write a callsign exception.
+    ///
+    /// # Arguments
+    ///
+    /// - `exception`: Callsign exception to write
+    ///
+    /// # Returns
+    ///
+    /// Nothing or an error
+    pub(crate) fn callsign_exception(
+        &mut self,
+        exception: &CallsignException,
+    ) -> Result<(), Error> {
+        self.writer.write_all(&exception.record.to_le_bytes())?;
+        self.string(&exception.call)?;
+        self.string(&exception.entity)?;
+        self.writer.write_all(&exception.adif.to_le_bytes())?;
+        self.location(
+            &exception.cqz,
+            &exception.cont,
+            &exception.long,
+            &exception.lat,
+        )?;
+        self.datetime_opt(&exception.start)?;
+        self.datetime_opt(&exception.end)
+    }
+
+    /// Write a prefix.
+    ///
+    /// # Arguments
+    ///
+    /// - `prefix`: Prefix to write
+    ///
+    /// # Returns
+    ///
+    /// Nothing or an error
+    pub(crate) fn prefix(&mut self, prefix: &Prefix) -> Result<(), Error> {
+        self.writer.write_all(&prefix.record.to_le_bytes())?;
+        self.string(&prefix.call)?;
+        self.string(&prefix.entity)?;
+        self.writer.write_all(&prefix.adif.to_le_bytes())?;
+        self.location(&prefix.cqz, &prefix.cont, &prefix.long, &prefix.lat)?;
+        self.datetime_opt(&prefix.start)?;
+        self.datetime_opt(&prefix.end)
+    }
+
+    /// Write an invalid operation.
+    ///
+    /// # Arguments
+    ///
+    /// - `operation`: Invalid operation to write
+    ///
+    /// # Returns
+    ///
+    /// Nothing or an error
+    pub(crate) fn invalid_operation(&mut self, operation: &InvalidOperation) -> Result<(), Error> {
+        self.writer.write_all(&operation.record.to_le_bytes())?;
+        self.string(&operation.call)?;
+        self.datetime_opt(&operation.start)?;
+        self.datetime_opt(&operation.end)
+    }
+
+    /// Write a CQ zone exception.
+    ///
+    /// # Arguments
+    ///
+    /// - `exception`: CQ zone exception to write
+    ///
+    /// # Returns
+    ///
+    /// Nothing or an error
+    pub(crate) fn zone_exception(&mut self, exception: &ZoneException) -> Result<(), Error> {
+        self.writer.write_all(&exception.record.to_le_bytes())?;
+        self.string(&exception.call)?;
+        self.writer.write_all(&[exception.zone])?;
+        self.datetime_opt(&exception.start)?;
+        self.datetime_opt(&exception.end)
+    }
+
+    /// Flush the underlying writer.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Nothing or an error
+    pub(crate) fn finish(mut self) -> Result<(), Error> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Write a length prefixed string
+    fn string(&mut self, value: &str) -> Result<(), Error> {
+        self.len(value.len())?;
+        self.writer.write_all(value.as_bytes())?;
+        Ok(())
+    }
+
+    /// Write a boolean as a single byte
+    fn bool(&mut self, value: bool) -> Result<(), Error> {
+        self.writer.write_all(&[value as u8])?;
+        Ok(())
+    }
+
+    /// Write a timestamp as seconds and nanoseconds since the unix epoch
+    fn datetime(&mut self, value: &DateTime<Utc>) -> Result<(), Error> {
+        self.writer.write_all(&value.timestamp().to_le_bytes())?;
+        self.writer
+            .write_all(&value.timestamp_subsec_nanos().to_le_bytes())?;
+        Ok(())
+    }
+
+    /// Write an optional timestamp
+    fn datetime_opt(&mut self, value: &Option<DateTime<Utc>>) -> Result<(), Error> {
+        self.option(value, |s, v| s.datetime(v))
+    }
+
+    /// Write the optional CQ zone, continent, longitude and latitude
+    fn location(
+        &mut self,
+        cqz: &Option<u8>,
+        cont: &Option<String>,
+        long: &Option<f32>,
+        lat: &Option<f32>,
+    ) -> Result<(), Error> {
+        self.option(cqz, |s, v| Ok(s.writer.write_all(&[*v])?))?;
+        self.option(cont, |s, v| s.string(v))?;
+        self.option(long, |s, v| Ok(s.writer.write_all(&v.to_le_bytes())?))?;
+        self.option(lat, |s, v| Ok(s.writer.write_all(&v.to_le_bytes())?))
+    }
+
+    /// Write an optional value prefixed by a presence flag
+    fn option<T>(
+        &mut self,
+        value: &Option<T>,
+        write: impl FnOnce(&mut Self, &T) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        match value {
+            Some(v) => {
+                self.bool(true)?;
+                write(self, v)
+            }
+            None => self.bool(false),
+        }
+    }
+}
+
+/// Reader for the snapshot format
+pub(crate) struct SnapshotReader<'a> {
+    /// Remaining data
+    data: &'a [u8],
+}
+
+impl<'a> SnapshotReader<'a> {
+    /// Create a new snapshot reader and check the magic bytes and the version of the header.
+    ///
+    /// # Arguments
+    ///
+    /// - `data`: Content of the snapshot
+    ///
+    /// # Returns
+    ///
+    /// Snapshot reader positioned at the timestamp of the ClubLog data or an error
+    pub(crate) fn new(data: &'a [u8]) -> Result<Self, Error> {
+        let mut reader = SnapshotReader { data };
+
+        if reader.take(SNAPSHOT_MAGIC.len()).ok() != Some(&SNAPSHOT_MAGIC[..]) {
+            return Err(Error::InvalidMagic);
+        }
+
+        let version = u16::from_le_bytes(reader.array()?);
+        if version != SNAPSHOT_VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        Ok(reader)
+    }
+
+    /// Read the timestamp of the ClubLog data.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Timestamp or an error
+    pub(crate) fn date(&mut self) -> Result<DateTime<Utc>, Error> {
+        self.datetime()
+    }
+
+    /// Read the length of a list.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Length of the list or an error
+    pub(crate) fn len(&mut self) -> Result<usize, Error> {
+        Ok(u32::from_le_bytes(self.array()?) as usize)
+    }
+
+    /// Read a child of a trie node.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Byte of the key leading to the child together with the index of the child node or an error
+    pub(crate) fn child(&mut self) -> Result<(u8, usize), Error> {
+        let byte = self.array::<1>()?[0];
+        Ok((byte, u32::from_le_bytes(self.array()?) as usize))
+    }
+
+    /// Read an entity.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Entity or an error
+    pub(crate) fn entity(&mut self) -> Result<Entity, Error> {
+        let adif = u16::from_le_bytes(self.array()?);
+        let name = self.string()?;
+        let prefix = self.string()?;
+        let deleted = self.bool()?;
+        let (cqz, cont, long, lat) = self.location()?;
+
+        Ok(Entity {
+            adif,
+            name,
+            prefix,
+            deleted,
+            cqz,
+            cont,
+            long,
+            lat,
+            start: self.datetime_opt()?,
+            end: self.datetime_opt()?,
+            whitelist: self.option(|s| s.bool())?,
+            whitelist_start: self.datetime_opt()?,
+            whitelist_end: self.datetime_opt()?,
+        })
+    }
+
+    /// Read a callsign exception.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Callsign exception or an error
+    pub(crate) fn callsign_exception(&mut self) -> Result<CallsignException, Error> {
+        let record = self.record()?;
+        let call = self.string()?;
+        let entity = self.string()?;
+        let adif = u16::from_le_bytes(self.array()?);
+        let (cqz, cont, long, lat) = self.location()?;
+
+        Ok(CallsignException {
+            record,
+            call,
+            entity,
+            adif,
+            cqz,
+            cont,
+            long,
+            lat,
+            start: self.datetime_opt()?,
+            end: self.datetime_opt()?,
+        })
+    }
+
+    /// Read a prefix.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Prefix or an error
+    pub(crate) fn prefix(&mut self) -> Result<Prefix, Error> {
+        let record = self.record()?;
+        let call = self.string()?;
+        let entity = self.string()?;
+        let adif = u16::from_le_bytes(self.array()?);
+        let (cqz, cont, long, lat) = self.location()?;
+
+        Ok(Prefix {
+            record,
+            call,
+            entity,
+            adif,
+            cqz,
+            cont,
+            long,
+            lat,
+            start: self.datetime_opt()?,
+            end: self.datetime_opt()?,
+        })
+    }
+
+    /// Read an invalid operation.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Invalid operation or an error
+    pub(crate) fn invalid_operation(&mut self) -> Result<InvalidOperation, Error> {
+        Ok(InvalidOperation {
+            record: self.record()?,
+            call: self.string()?,
+            start: self.datetime_opt()?,
+            end: self.datetime_opt()?,
+        })
+    }
+
+    /// Read a CQ zone exception.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// CQ zone exception or an error
+    pub(crate) fn zone_exception(&mut self) -> Result<ZoneException, Error> {
+        Ok(ZoneException {
+            record: self.record()?,
+            call: self.string()?,
+            zone: self.array::<1>()?[0],
+            start: self.datetime_opt()?,
+            end: self.datetime_opt()?,
+        })
+    }
+
+    /// Take the given number of bytes from the remaining data
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.data.len() < len {
+            return Err(Error::Corrupted);
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    /// Take a fixed number of bytes from the remaining data
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        // Unwrap is safe here, since the slice has the requested length
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    /// Read a record identifier
+    fn record(&mut self) -> Result<RecordId, Error> {
+        Ok(RecordId::from_le_bytes(self.array()?))
+    }
+
+    /// Read a length prefixed string
+    fn string(&mut self) -> Result<String, Error> {
+        let len = self.len()?;
+        let raw = self.take(len)?;
+        String::from_utf8(raw.to_vec()).map_err(|_| Error::Corrupted)
+    }
+
+    /// Read a boolean from a single byte
+    fn bool(&mut self) -> Result<bool, Error> {
+        match self.array::<1>()?[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(Error::Corrupted),
+        }
+    }
+
+    /// Read a timestamp from seconds and nanoseconds since the unix epoch
+    fn datetime(&mut self) -> Result<DateTime<Utc>, Error> {
+        let secs = i64::from_le_bytes(self.array()?);
+        let nanos = u32::from_le_bytes(self.array()?);
+        DateTime::from_timestamp(secs, nanos).ok_or(Error::Corrupted)
+    }
+
+    /// Read an optional timestamp
+    fn datetime_opt(&mut self) -> Result<Option<DateTime<Utc>>, Error> {
+        self.option(|s| s.datetime())
+    }
+
+    /// Read the optional CQ zone, continent, longitude and latitude
+    #[allow(clippy::type_complexity)]
+    fn location(
+        &mut self,
+    ) -> Result<(Option<u8>, Option<String>, Option<f32>, Option<f32>), Error> {
+        Ok((
+            self.option(|s| Ok(s.array::<1>()?[0]))?,
+            self.option(|s| s.string())?,
+            self.option(|s| Ok(f32::from_le_bytes(s.array()?)))?,
+            self.option(|s| Ok(f32::from_le_bytes(s.array()?)))?,
+        ))
+    }
+
+    /// Read an optional value prefixed by a presence flag
+    fn option<T>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<T, Error>,
+    ) -> Result<Option<T>, Error> {
+        if self.bool()? {
+            read(self).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
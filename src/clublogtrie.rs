@@ -0,0 +1,292 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Prefix trie used as index for the prefixes of the ClubLog data.
+//!
+//! Each node represents a string, which is the path of bytes from the root to the node.
+//! All values stored for a key are kept together within the node of the key.
+//! Walking a callsign through the trie visits all keys that are a prefix of the callsign, ordered by their length.
+
+/// Single node of the trie
+#[derive(Debug, PartialEq)]
+struct Node<T> {
+    /// Byte of the key leading to the child node together with the index of the child node
+    children: Vec<(u8, usize)>,
+    /// Values stored for the key of the node
+    values: Vec<T>,
+}
+
+impl<T> Node<T> {
+    /// Create a new node without children and values.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Empty node
+    fn new() -> Self {
+        Node {
+            children: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+}
+
+/// Prefix trie with multiple values per key
+#[derive(Debug, PartialEq)]
+pub(crate) struct PrefixTrie<T> {
+    /// All nodes, the root node is at index zero
+    nodes: Vec<Node<T>>,
+}
+
+impl<T> PrefixTrie<T> {
+    /// Create a new empty trie.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Empty trie
+    pub(crate) fn new() -> Self {
+        PrefixTrie {
+            nodes: vec![Node::new()],
+        }
+    }
+
+    /// Add a value for a key.
+    /// Values of the same key keep the order in which they were added.
+    ///
+    /// # Arguments
+    ///
+    /// - `key`: Key of the value
+    /// - `value`: Value to add
+    ///
+    /// # Returns
+    ///
+    /// (None)
+    pub(crate) fn insert(&mut self, key: &str, value: T) {
+        let mut node = 0;
+        for c in key.bytes() {
+            node = match self.child(node, c) {
+                Some(child) => child,
+                None => {
+                    self.nodes.push(Node::new());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.push((c, child));
+                    child
+                }
+            };
+        }
+
+        self.nodes[node].values.push(value);
+    }
+
+    /// Get all values of a key.
+    ///
+    /// # Arguments
+    ///
+    /// - `key`: Key to search for
+    ///
+    /// # Returns
+    ///
+    /// Values of the key, empty if the key is unknown
+    pub(crate) fn get(&self, key: &str) -> &[T] {
+        self.walk(0, key)
+            .map(|node| self.nodes[node].values.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Walk a key through the trie and report each node on the way.
+    /// The walk ends as soon as the key leaves the trie.
+    ///
+    /// # Arguments
+    ///
+    /// - `key`: Key to walk through the trie
+    ///
+    /// # Returns
+    ///
+    /// Iterator over the length of the visited part of the key together with the node, ordered by the length
+    pub(crate) fn path<'a>(&'a self, key: &'a str) -> impl Iterator<Item = (usize, Cursor)> + 'a {
+        key.bytes()
+            .scan(0, move |node, c| {
+                *node = self.child(*node, c)?;
+                Some(Cursor(*node))
+            })
+            .enumerate()
+            .map(|(idx, cursor)| (idx + 1, cursor))
+    }
+
+    /// Walk the remaining part of a key, starting from a node visited before.
+    ///
+    /// # Arguments
+    ///
+    /// - `cursor`: Node to start from
+    /// - `rest`: Remaining part of the key
+    ///
+    /// # Returns
+    ///
+    /// Node of the complete key, `None` if the key is not part of the trie
+    pub(crate) fn descend(&self, cursor: Cursor, rest: &str) -> Option<Cursor> {
+        self.walk(cursor.0, rest).map(Cursor)
+    }
+
+    /// Get the values of a node.
+    ///
+    /// # Arguments
+    ///
+    /// - `cursor`: Node to get the values of
+    ///
+    /// # Returns
+    ///
+    /// Values of the node, empty if no value is stored for the key of the node
+    pub(crate) fn values_at(&self, cursor: Cursor) -> &[T] {
+        &self.nodes[cursor.0].values
+    }
+
+    /// Iterate over all values of the trie.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Iterator over all values
+    pub(crate) fn values(&self) -> impl Iterator<Item = &T> {
+        self.nodes.iter().flat_map(|n| n.values.iter())
+    }
+
+    /// Iterate over all nodes ordered by their index, e.g. to store the trie as it is.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Iterator over the children and the values of each node
+    #[cfg(feature = "snapshot")]
+    pub(crate) fn nodes(&self) -> impl Iterator<Item = (&[(u8, usize)], &[T])> {
+        self.nodes
+            .iter()
+            .map(|n| (n.children.as_slice(), n.values.as_slice()))
+    }
+
+    /// Assemble a trie from its nodes as returned by [PrefixTrie::nodes].
+    ///
+    /// # Arguments
+    ///
+    /// - `nodes`: Children and values of each node, ordered by the index of the node
+    ///
+    /// # Returns
+    ///
+    /// Trie or `None` if the nodes do not form a valid trie
+    #[cfg(feature = "snapshot")]
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn from_nodes(nodes: Vec<(Vec<(u8, usize)>, Vec<T>)>) -> Option<Self> {
+        // Children are always added after their parent, which also rules out cycles
+        let count = nodes.len();
+        let valid = count > 0
+            && nodes.iter().enumerate().all(|(idx, (children, _))| {
+                children
+                    .iter()
+                    .all(|(_, child)| *child > idx && *child < count)
+            });
+        if !valid {
+            return None;
+        }
+
+        Some(PrefixTrie {
+            nodes: nodes
+                .into_iter()
+                .map(|(children, values)| Node { children, values })
+                .collect(),
+        })
+    }
+
+    /// Get the total number of values.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Number of values
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.nodes.iter().map(|n| n.values.len()).sum()
+    }
+
+    /// Get the child node reached by a single byte.
+    ///
+    /// # Arguments
+    ///
+    /// - `node`: Index of the parent node
+    /// - `c`: Byte of the key
+    ///
+    /// # Returns
+    ///
+    /// Index of the child node, `None` if there is none
+    fn child(&self, node: usize, c: u8) -> Option<usize> {
+        self.nodes[node]
+            .children
+            .iter()
+            .find(|(b, _)| *b == c)
+            .map(|(_, child)| *child)
+    }
+
+    /// Walk a key starting from a node.
+    ///
+    /// # Arguments
+    ///
+    /// - `node`: Index of the node to start from
+    /// - `key`: Key to walk
+    ///
+    /// # Returns
+    ///
+    /// Index of the reached node, `None` if the key leaves the trie
+    fn walk(&self, node: usize, key: &str) -> Option<usize> {
+        key.bytes().try_fold(node, |node, c| self.child(node, c))
+    }
+}
+
+/// Reference to a node of a [PrefixTrie]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Cursor(usize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trie_lookup() {
+        let mut trie = PrefixTrie::new();
+        trie.insert("U", 1);
+        trie.insert("UA9", 2);
+        trie.insert("UA9", 3);
+        trie.insert("SV/A", 4);
+
+        assert_eq!(trie.len(), 4);
+        assert_eq!(trie.get("UA9"), &[2, 3]);
+        assert!(trie.get("UA").is_empty());
+        assert!(trie.get("DL").is_empty());
+
+        let matches: Vec<usize> = trie
+            .path("UA9ABC")
+            .filter(|(_, c)| !trie.values_at(*c).is_empty())
+            .map(|(len, _)| len)
+            .collect();
+        assert_eq!(matches, vec![1, 3]);
+
+        let (len, cursor) = trie.path("SV1ABC").last().unwrap();
+        assert_eq!(len, 2);
+        assert_eq!(trie.values_at(trie.descend(cursor, "/A").unwrap()), &[4]);
+        assert!(trie.descend(cursor, "/B").is_none());
+    }
+}
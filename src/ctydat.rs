@@ -0,0 +1,696 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Parser for the country files `cty.dat` and `cty.csv` (Big CTY) maintained by Jim Reisert, AD1C.
+//!
+//! The parsed data is converted into the ClubLog representation and implements the [ClubLogQuery] trait.
+//! Therefore the callsign analyzer of the module [call](crate::call) works unchanged on this data.
+//!
+//! Compared to the ClubLog data, a few differences exist:
+//! - Alias prefixes are converted into [prefixes](Prefix), exact callsigns (`=` entries) into [callsign exceptions](CallsignException).
+//!   Overrides of the CQ zone, continent and coordinates are applied to the single prefix or callsign exception.
+//! - The files contain neither time windows, invalid operations nor whitelists.
+//! - Record identifiers are not part of the files and are assigned in the order of appearance.
+//! - The ITU zone and the UTC offset have no equivalent in the ClubLog data and are available by [details](CtyDat::details).
+//! - The longitude is converted into the ClubLog convention, where east is positive.
+//! - The files store the UTC offset with an inverted sign, it is converted to the offset of the local time to UTC (e.g. `1.0` for Germany).
+//! - The file `cty.dat` does not contain ADIF identifiers. Therefore the caller must provide a function to resolve the ADIF identifier by the primary prefix of an entity.
+//!   The file `cty.csv` already contains the ADIF identifiers.
+
+use crate::clublog::{
+    Adif, CallsignException, CallsignExceptions, ClubLog, CqZone, Entities, Entity,
+    InvalidOperation, InvalidOperations, Prefix, Prefixes, RecordId, ZoneException, ZoneExceptions,
+};
+use crate::clublogmap::ClubLogMap;
+use crate::clublogquery::{ClubLogQuery, QueryIter};
+use chrono::{DateTime, NaiveDate, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+
+/// Possible errors while parsing a country file
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    /// Entry does not follow the format of the file
+    #[error("Invalid format in line {line}: {reason}")]
+    InvalidFormat {
+        /// Line of the entry
+        line: usize,
+        /// Description of the problem
+        reason: &'static str,
+    },
+
+    /// Value is not a valid number
+    #[error("Invalid number '{value}' in line {line}")]
+    InvalidNumber {
+        /// Line of the entry
+        line: usize,
+        /// Raw value
+        value: String,
+    },
+
+    /// ADIF identifier of an entity could not be resolved
+    #[error("Unknown ADIF identifier for entity with prefix '{prefix}' in line {line}")]
+    UnknownEntity {
+        /// Line of the entry
+        line: usize,
+        /// Primary prefix of the entity
+        prefix: String,
+    },
+}
+
+/// Information of the country files without an equivalent in the ClubLog data
+#[derive(Debug, PartialEq, Clone)]
+pub struct CtyDetails {
+    /// ITU zone
+    pub ituz: u8,
+    /// Offset of the local time to UTC in hours
+    pub utc_offset: f32,
+}
+
+/// Country file data converted into the ClubLog representation
+#[derive(Debug)]
+pub struct CtyDat {
+    /// Converted data
+    data: ClubLogMap,
+    /// Details by record identifier of prefixes and callsign exceptions
+    details: HashMap<RecordId, CtyDetails>,
+}
+
+/// Fields of an entity line shared by both file formats
+struct EntityLine<'a> {
+    /// Line of the entry
+    line: usize,
+    /// Name of entity
+    name: &'a str,
+    /// Primary prefix, may begin with `*` for entities that are not on the DXCC list
+    prefix: &'a str,
+    /// ADIF identifier
+    adif: Adif,
+    /// Continent
+    cont: &'a str,
+    /// CQ zone
+    cqz: &'a str,
+    /// ITU zone
+    ituz: &'a str,
+    /// Latitude
+    lat: &'a str,
+    /// Longitude, west is positive
+    long: &'a str,
+    /// UTC offset
+    utc_offset: &'a str,
+    /// Alias prefixes and exact callsigns
+    aliases: &'a str,
+}
+
+impl CtyDat {
+    /// Parse the content of a `cty.dat` file.
+    ///
+    /// # Arguments
+    ///
+    /// - `content`: Content of the file
+    /// - `resolve_adif`: Function to get the ADIF identifier of an entity by its primary prefix (without a leading `*`)
+    ///
+    /// # Returns
+    ///
+    /// Parsed data or an error
+    pub fn parse<F>(content: &str, resolve_adif: F) -> Result<Self, Error>
+    where
+        F: Fn(&str) -> Option<Adif>,
+    {
+        let mut builder = Builder::default();
+
+        let mut line = 1;
+        for entry in content.split_inclusive(';') {
+            let leading = entry.len() - entry.trim_start().len();
+            let entry_line = line + entry[..leading].matches('\n').count();
+            line += entry.matches('\n').count();
+
+            if entry.trim().is_empty() {
+                continue;
+            }
+
+            let entry = entry.trim();
+            let Some(entry) = entry.strip_suffix(';') else {
+                return Err(Error::InvalidFormat {
+                    line: entry_line,
+                    reason: "entry is not terminated by ';'",
+                });
+            };
+
+            let fields: Vec<&str> = entry.splitn(9, ':').map(str::trim).collect();
+            if fields.len() != 9 {
+                return Err(Error::InvalidFormat {
+                    line: entry_line,
+                    reason: "expected eight fields separated by ':' followed by the alias prefixes",
+                });
+            }
+
+            let prefix = fields[7];
+            let adif = resolve_adif(prefix.trim_start_matches('*')).ok_or_else(|| {
+                Error::UnknownEntity {
+                    line: entry_line,
+                    prefix: String::from(prefix),
+                }
+            })?;
+
+            builder.add(EntityLine {
+                line: entry_line,
+                name: fields[0],
+                prefix,
+                adif,
+                cqz: fields[1],
+                ituz: fields[2],
+                cont: fields[3],
+                lat: fields[4],
+                long: fields[5],
+                utc_offset: fields[6],
+                aliases: fields[8],
+            })?;
+        }
+
+        Ok(builder.finish())
+    }
+
+    /// Parse the content of a `cty.csv` file.
+    ///
+    /// # Arguments
+    ///
+    /// - `content`: Content of the file
+    ///
+    /// # Returns
+    ///
+    /// Parsed data or an error
+    pub fn parse_csv(content: &str) -> Result<Self, Error> {
+        let mut builder = Builder::default();
+
+        for (idx, raw) in content.lines().enumerate() {
+            let line = idx + 1;
+            let raw = raw.trim();
+            if raw.is_empty() {
+                continue;
+            }
+
+            // The name of the entity is the only field that may contain a comma, therefore assign the fields from both sides
+            let fields: Vec<&str> = raw.split(',').collect();
+            let n = fields.len();
+            if n < 10 {
+                return Err(Error::InvalidFormat {
+                    line,
+                    reason: "expected ten fields separated by ','",
+                });
+            }
+            let fields_tail: Vec<&str> = fields[n - 8..].iter().map(|f| f.trim()).collect();
+            let Some(aliases) = fields_tail[7].strip_suffix(';') else {
+                return Err(Error::InvalidFormat {
+                    line,
+                    reason: "entry is not terminated by ';'",
+                });
+            };
+            let name = fields[1..n - 8].join(",");
+
+            builder.add(EntityLine {
+                line,
+                name: name.trim(),
+                prefix: fields[0].trim(),
+                adif: parse_number(fields_tail[0], line)?,
+                cont: fields_tail[1],
+                cqz: fields_tail[2],
+                ituz: fields_tail[3],
+                lat: fields_tail[4],
+                long: fields_tail[5],
+                utc_offset: fields_tail[6],
+                aliases,
+            })?;
+        }
+
+        Ok(builder.finish())
+    }
+
+    /// Get the timestamp of the data.
+    /// The timestamp is taken from the special `=VERyyyymmdd` entry, if present.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Timestamp of the data
+    pub fn date(&self) -> DateTime<Utc> {
+        self.data.date()
+    }
+
+    /// Get the information without an equivalent in the ClubLog data like the ITU zone.
+    ///
+    /// # Arguments
+    ///
+    /// - `record`: Record identifier of a prefix or callsign exception
+    ///
+    /// # Returns
+    ///
+    /// Details, if present
+    pub fn details(&self, record: RecordId) -> Option<&CtyDetails> {
+        self.details.get(&record)
+    }
+}
+
+impl ClubLogQuery for CtyDat {
+    fn get_entity(&self, adif: Adif, timestamp: &DateTime<Utc>) -> Option<&Entity> {
+        self.data.get_entity(adif, timestamp)
+    }
+
+    fn get_prefix(&self, prefix: &str, timestamp: &DateTime<Utc>) -> Option<&Prefix> {
+        self.data.get_prefix(prefix, timestamp)
+    }
+
+    fn longest_prefix_match(&self, call: &str, timestamp: &DateTime<Utc>) -> Option<&Prefix> {
+        self.data.longest_prefix_match(call, timestamp)
+    }
+
+    fn longest_prefix_match_with_appendix(
+        &self,
+        call: &str,
+        appendix: &str,
+        timestamp: &DateTime<Utc>,
+    ) -> Option<&Prefix> {
+        self.data
+            .longest_prefix_match_with_appendix(call, appendix, timestamp)
+    }
+
+    fn get_callsign_exception(
+        &self,
+        callsign: &str,
+        timestamp: &DateTime<Utc>,
+    ) -> Option<&CallsignException> {
+        self.data.get_callsign_exception(callsign, timestamp)
+    }
+
+    fn get_zone_exception(&self, callsign: &str, timestamp: &DateTime<Utc>) -> Option<CqZone> {
+        self.data.get_zone_exception(callsign, timestamp)
+    }
+
+    fn is_invalid_operation(&self, callsign: &str, timestamp: &DateTime<Utc>) -> bool {
+        self.data.is_invalid_operation(callsign, timestamp)
+    }
+
+    fn iter_entities(&self) -> QueryIter<'_, Entity> {
+        self.data.iter_entities()
+    }
+
+    fn iter_prefixes(&self) -> QueryIter<'_, Prefix> {
+        self.data.iter_prefixes()
+    }
+
+    fn iter_callsign_exceptions(&self) -> QueryIter<'_, CallsignException> {
+        self.data.iter_callsign_exceptions()
+    }
+
+    fn iter_zone_exceptions(&self) -> QueryIter<'_, ZoneException> {
+        self.data.iter_zone_exceptions()
+    }
+
+    fn iter_invalid_operations(&self) -> QueryIter<'_, InvalidOperation> {
+        self.data.iter_invalid_operations()
+    }
+
+    fn supports_history(&self) -> bool {
+        self.data.supports_history()
+    }
+
+    fn get_entity_by_prefix(&self, prefix: &str, timestamp: &DateTime<Utc>) -> Option<&Entity> {
+        self.data.get_entity_by_prefix(prefix, timestamp)
+    }
+
+    fn entities(&self, timestamp: &DateTime<Utc>) -> QueryIter<'_, Entity> {
+        self.data.entities(timestamp)
+    }
+
+    fn search_entities<'a>(&'a self, name: &str) -> QueryIter<'a, Entity> {
+        self.data.search_entities(name)
+    }
+
+    fn prefixes_by_adif(&self, adif: Adif) -> QueryIter<'_, Prefix> {
+        self.data.prefixes_by_adif(adif)
+    }
+
+    fn exceptions_by_adif(&self, adif: Adif) -> QueryIter<'_, CallsignException> {
+        self.data.exceptions_by_adif(adif)
+    }
+
+    fn prefix_history(&self, prefix: &str) -> Vec<&Prefix> {
+        self.data.prefix_history(prefix)
+    }
+
+    fn callsign_exception_history(&self, callsign: &str) -> Vec<&CallsignException> {
+        self.data.callsign_exception_history(callsign)
+    }
+
+    fn zone_exception_history(&self, callsign: &str) -> Vec<&ZoneException> {
+        self.data.zone_exception_history(callsign)
+    }
+
+    fn invalid_operation_history(&self, callsign: &str) -> Vec<&InvalidOperation> {
+        self.data.invalid_operation_history(callsign)
+    }
+}
+
+/// Collects the converted entries of a country file
+#[derive(Default)]
+struct Builder {
+    /// Timestamp taken from the version entry
+    date: Option<DateTime<Utc>>,
+    /// List of entities
+    entities: Vec<Entity>,
+    /// ADIF identifiers of the entities already added
+    adifs: HashSet<Adif>,
+    /// List of prefixes
+    prefixes: Vec<Prefix>,
+    /// List of callsign exceptions
+    exceptions: Vec<CallsignException>,
+    /// Details by record identifier
+    details: HashMap<RecordId, CtyDetails>,
+}
+
+impl Builder {
+    /// Convert a single entity together with its alias prefixes.
+    ///
+    /// # Arguments
+    ///
+    /// - `entry`: Fields of the entity line
+    ///
+    /// # Returns
+    ///
+    /// Nothing or an error
+    fn add(&mut self, entry: EntityLine) -> Result<(), Error> {
+        lazy_static! {
+            static ref RE_ALIAS: Regex = Regex::new(
+                r"^(=?)([A-Z0-9/]+)((?:\(\d+\)|\[\d+\]|<[-+\d.]+/[-+\d.]+>|\{[A-Z]{2}\}|~[-+\d.]+~)*)$"
+            )
+            .unwrap();
+            static ref RE_OVERRIDE: Regex =
+                Regex::new(r"\((\d+)\)|\[(\d+)\]|<([-+\d.]+)/([-+\d.]+)>|\{([A-Z]{2})\}|~([-+\d.]+)~")
+                    .unwrap();
+        }
+
+        let line = entry.line;
+        let cqz: CqZone = parse_number(entry.cqz, line)?;
+        let ituz: u8 = parse_number(entry.ituz, line)?;
+        let lat: f32 = parse_number(entry.lat, line)?;
+        let long: f32 = -parse_number::<f32>(entry.long, line)?;
+        let utc_offset: f32 = -parse_number::<f32>(entry.utc_offset, line)?;
+        let prefix = entry.prefix.trim_start_matches('*').to_uppercase();
+
+        // Entities not on the DXCC list may share the ADIF identifier with their parent entity
+        if self.adifs.insert(entry.adif) {
+            self.entities.push(Entity {
+                adif: entry.adif,
+                name: String::from(entry.name),
+                prefix: prefix.clone(),
+                deleted: false,
+                cqz: Some(cqz),
+                cont: Some(String::from(entry.cont)),
+                long: Some(long),
+                lat: Some(lat),
+                start: None,
+                end: None,
+                whitelist: None,
+                whitelist_start: None,
+                whitelist_end: None,
+            });
+        }
+
+        let mut aliases: Vec<String> = entry
+            .aliases
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|a| !a.is_empty())
+            .map(str::to_uppercase)
+            .collect();
+        if !aliases.contains(&prefix) {
+            aliases.insert(0, prefix);
+        }
+
+        for alias in aliases.iter() {
+            let caps = RE_ALIAS.captures(alias).ok_or(Error::InvalidFormat {
+                line,
+                reason: "invalid alias prefix",
+            })?;
+
+            let exact = !caps[1].is_empty();
+            let call = String::from(&caps[2]);
+
+            let mut record_cqz = cqz;
+            let mut record_ituz = ituz;
+            let mut record_lat = lat;
+            let mut record_long = long;
+            let mut record_cont = String::from(entry.cont);
+            let mut record_utc_offset = utc_offset;
+            for ovr in RE_OVERRIDE.captures_iter(&caps[3]) {
+                if let Some(v) = ovr.get(1) {
+                    record_cqz = parse_number(v.as_str(), line)?;
+                } else if let Some(v) = ovr.get(2) {
+                    record_ituz = parse_number(v.as_str(), line)?;
+                } else if let (Some(la), Some(lo)) = (ovr.get(3), ovr.get(4)) {
+                    record_lat = parse_number(la.as_str(), line)?;
+                    record_long = -parse_number::<f32>(lo.as_str(), line)?;
+                } else if let Some(v) = ovr.get(5) {
+                    record_cont = String::from(v.as_str());
+                } else if let Some(v) = ovr.get(6) {
+                    record_utc_offset = -parse_number::<f32>(v.as_str(), line)?;
+                }
+            }
+
+            let record = (self.prefixes.len() + self.exceptions.len() + 1) as RecordId;
+            self.details.insert(
+                record,
+                CtyDetails {
+                    ituz: record_ituz,
+                    utc_offset: record_utc_offset,
+                },
+            );
+
+            if exact {
+                if let Some(date) = parse_version(&call) {
+                    self.date = Some(date);
+                }
+
+                self.exceptions.push(CallsignException {
+                    record,
+                    call,
+                    entity: String::from(entry.name),
+                    adif: entry.adif,
+                    cqz: Some(record_cqz),
+                    cont: Some(record_cont),
+                    long: Some(record_long),
+                    lat: Some(record_lat),
+                    start: None,
+                    end: None,
+                });
+            } else {
+                self.prefixes.push(Prefix {
+                    record,
+                    call,
+                    entity: String::from(entry.name),
+                    adif: entry.adif,
+                    cqz: Some(record_cqz),
+                    cont: Some(record_cont),
+                    long: Some(record_long),
+                    lat: Some(record_lat),
+                    start: None,
+                    end: None,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finish the conversion.
+    ///
+    /// # Arguments
+    ///
+    /// (None)
+    ///
+    /// # Returns
+    ///
+    /// Converted data
+    fn finish(self) -> CtyDat {
+        let clublog = ClubLog {
+            date: self.date.unwrap_or_default(),
+            entities: Entities {
+                list: self.entities,
+            },
+            exceptions: CallsignExceptions {
+                list: self.exceptions,
+            },
+            prefixes: Prefixes {
+                list: self.prefixes,
+            },
+            invalid_operations: InvalidOperations { list: Vec::new() },
+            zone_exceptions: ZoneExceptions { list: Vec::new() },
+        };
+
+        CtyDat {
+            data: ClubLogMap::from(clublog),
+            details: self.details,
+        }
+    }
+}
+
+/// Parse a number of a country file.
+///
+/// # Arguments
+///
+/// - `value`: Raw value
+/// - `line`: Line of the entry, used for error reporting
+///
+/// # Returns
+///
+/// Parsed number or an error
+fn parse_number<T: std::str::FromStr>(value: &str, line: usize) -> Result<T, Error> {
+    value.trim().parse().map_err(|_| Error::InvalidNumber {
+        line,
+        value: String::from(value),
+    })
+}
+
+/// Parse the timestamp of the special version entry like `VER20231120`.
+///
+/// # Arguments
+///
+/// - `call`: Exact callsign entry
+///
+/// # Returns
+///
+/// Timestamp, if the entry is a version entry
+fn parse_version(call: &str) -> Option<DateTime<Utc>> {
+    let date = call.strip_prefix("VER")?;
+    if date.len() != 8 {
+        return None;
+    }
+
+    Some(
+        NaiveDate::parse_from_str(date, "%Y%m%d")
+            .ok()?
+            .and_hms_opt(0, 0, 0)?
+            .and_utc(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::call::analyze_callsign;
+
+    const SAMPLE_DAT: &str =
+        "Fed. Rep. of Germany:     14:  28:  EU:   51.00:   -10.00:    -1.0:  DL:
+    DA,DB,DC,DD,DE,DF,DG,DH,DI,DJ,DK,DL,DM,DN,DO,DP,DQ,DR,Y2,Y3,Y4,Y5,Y6,Y7,Y8,Y9,
+    =DL0POL(15)[29];
+Canada:                   05:  09:  NA:   44.35:    78.75:     5.0:  VE:
+    CF,CG,CJ,CK,VA,VB,VC,VD,VE,VF,VG,VO,VX,VY,XJ,XK,XL,XM,XN,XO,
+    VE8(1)[4]<65.0/120.0>{NA}~7.0~,=VER20231120;
+";
+
+    const SAMPLE_CSV: &str =
+        "DL,Fed. Rep. of Germany,230,EU,14,28,51.00,-10.00,-1.0,DA DB DL =DL0POL(15)[29];
+VE,Canada, the north,1,NA,5,9,44.35,78.75,5.0,VA VE VE8(1)[4];
+";
+
+    fn resolve_adif(prefix: &str) -> Option<Adif> {
+        match prefix {
+            "DL" => Some(230),
+            "VE" => Some(1),
+            _ => None,
+        }
+    }
+
+    fn timestamp() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2020-01-01T00:00:00Z")
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn parse_dat() {
+        let cty = CtyDat::parse(SAMPLE_DAT, resolve_adif).unwrap();
+
+        let entity = cty.get_entity(230, &timestamp()).unwrap();
+        assert_eq!(entity.prefix, "DL");
+        assert_eq!(entity.long, Some(10.0));
+
+        let prefix = cty.get_prefix("VE8", &timestamp()).unwrap();
+        assert_eq!(prefix.cqz, Some(1));
+        assert_eq!(prefix.long, Some(-120.0));
+        let details = cty.details(prefix.record).unwrap();
+        assert_eq!(details.ituz, 4);
+        assert_eq!(details.utc_offset, -7.0);
+        let prefix = cty.get_prefix("DL", &timestamp()).unwrap();
+        assert_eq!(cty.details(prefix.record).unwrap().utc_offset, 1.0);
+
+        let exception = cty.get_callsign_exception("DL0POL", &timestamp()).unwrap();
+        assert_eq!(exception.cqz, Some(15));
+        assert_eq!(cty.details(exception.record).unwrap().ituz, 29);
+
+        assert_eq!(cty.date().to_rfc3339(), "2023-11-20T00:00:00+00:00");
+    }
+
+    #[test]
+    fn parse_csv() {
+        let cty = CtyDat::parse_csv(SAMPLE_CSV).unwrap();
+
+        assert_eq!(
+            cty.get_entity(1, &timestamp()).unwrap().name,
+            "Canada, the north"
+        );
+        assert_eq!(cty.get_prefix("DB", &timestamp()).unwrap().adif, 230);
+        assert_eq!(cty.get_prefix("VE8", &timestamp()).unwrap().cqz, Some(1));
+        assert!(cty.get_callsign_exception("DL0POL", &timestamp()).is_some());
+    }
+
+    #[test]
+    fn analyze() {
+        let cty = CtyDat::parse(SAMPLE_DAT, resolve_adif).unwrap();
+
+        let calls = [
+            ("DL1ABC", 230, 14),
+            ("VE8ABC", 1, 1),
+            ("DL0POL", 230, 15),
+            ("VE3ABC/DL", 230, 14),
+        ];
+
+        for call in calls.iter() {
+            let res = analyze_callsign(&cty, call.0, &timestamp()).unwrap();
+            assert_eq!(res.adif, call.1);
+            assert_eq!(res.cqzone, Some(call.2));
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        let res = CtyDat::parse(SAMPLE_DAT, |_| None);
+        assert_eq!(
+            res.unwrap_err(),
+            Error::UnknownEntity {
+                line: 1,
+                prefix: String::from("DL")
+            }
+        );
+
+        let res = CtyDat::parse(
+            &SAMPLE_DAT.replace("=VER20231120;", "=VER20231120"),
+            resolve_adif,
+        );
+        assert!(matches!(res, Err(Error::InvalidFormat { line: 4, .. })));
+
+        let res = CtyDat::parse(&SAMPLE_DAT.replace("05:", "X5:"), resolve_adif);
+        assert_eq!(
+            res.unwrap_err(),
+            Error::InvalidNumber {
+                line: 4,
+                value: String::from("X5")
+            }
+        );
+    }
+}
@@ -5,9 +5,25 @@
 //! This crate provides a few modules to work with ham radio callsigns.
 //! The first module [clublog] implements a parser for the ClubLog XML data and further implements the [ClubLogQuery](clublogquery::ClubLogQuery) trait.
 //! For faster access, the module [clublogmap] implements the trait based on HashMaps.
+//! As an alternative data source, the module [ctydat] implements a parser for the `cty.dat` and `cty.csv` country files that also implements the trait.
 //! Using the trait, the module [call] provides an analyzer for a callsign to get further information like the entity or the continent.
 
 pub mod call;
+pub mod callanalyzer;
+pub mod callnormalize;
+pub mod callparts;
+pub mod callrules;
 pub mod clublog;
+pub mod clublogdiff;
+#[cfg(feature = "fetch")]
+pub mod clublogfetch;
+pub mod clubloghandle;
+pub mod clubloglint;
 pub mod clublogmap;
+pub mod clublogoverlay;
+mod clublogparser;
 pub mod clublogquery;
+#[cfg(feature = "snapshot")]
+pub mod clublogsnapshot;
+mod clublogtrie;
+pub mod ctydat;
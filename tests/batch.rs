@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use hamcall::call::{analyze_callsigns, CallsignError};
+use hamcall::clublog::ClubLog;
+use hamcall::clublogmap::ClubLogMap;
+
+fn read_sample() -> ClubLogMap {
+    ClubLogMap::from(ClubLog::from_path("tests/data/cty_sample.xml").unwrap())
+}
+
+fn calls() -> Vec<(String, DateTime<Utc>)> {
+    let timestamp = |t| DateTime::parse_from_rfc3339(t).unwrap().into();
+
+    (0..1000)
+        .flat_map(|i| {
+            [
+                (
+                    format!("DL{}ABC", i % 10),
+                    timestamp("2020-01-01T00:00:00Z"),
+                ),
+                (
+                    format!("VE{}XYZ", i % 10),
+                    timestamp("2020-01-01T00:00:00Z"),
+                ),
+                (String::from("DL0XX"), timestamp("2001-06-01T00:00:00Z")),
+                (String::from("XX1ABC"), timestamp("2020-01-01T00:00:00Z")),
+            ]
+        })
+        .collect()
+}
+
+#[test]
+fn batch_in_order() {
+    let clublog = read_sample();
+    let calls = calls();
+
+    let results = analyze_callsigns(&clublog, calls.iter().map(|(c, t)| (c, *t)));
+
+    assert_eq!(results.len(), calls.len());
+    for (idx, result) in results.iter().enumerate() {
+        match idx % 4 {
+            0 => assert_eq!(result.as_ref().unwrap().adif, 230),
+            1 => assert_eq!(result.as_ref().unwrap().adif, 1),
+            2 => assert!(matches!(
+                result,
+                Err(CallsignError::InvalidOperation {
+                    record: Some(20),
+                    ..
+                })
+            )),
+            _ => assert_eq!(
+                result,
+                &Err(CallsignError::BeginWithoutPrefix {
+                    part: String::from("XX1ABC"),
+                    index: 0
+                })
+            ),
+        }
+        if let Ok(call) = result {
+            assert_eq!(call.call, calls[idx].0);
+        }
+    }
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn batch_parallel() {
+    use hamcall::call::analyze_callsigns_parallel;
+
+    let clublog = read_sample();
+    let calls = calls();
+
+    let serial = analyze_callsigns(&clublog, calls.clone());
+    let parallel = analyze_callsigns_parallel(&clublog, calls);
+
+    assert_eq!(serial, parallel);
+}
@@ -0,0 +1,121 @@
+#![cfg(feature = "fetch")]
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use hamcall::clublogfetch::{Error, Fetcher, Refresh};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+const SAMPLE: &str = include_str!("data/cty_sample.xml");
+
+fn gzip(data: &str) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data.as_bytes()).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// Serve the given responses, one per connection, and report the received request heads
+fn stub_server(responses: Vec<Vec<u8>>) -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/cty.php", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        for response in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+
+            let mut request = String::new();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" || line.is_empty() {
+                    break;
+                }
+                request.push_str(&line);
+            }
+
+            stream.write_all(&response).unwrap();
+            tx.send(request).unwrap();
+        }
+    });
+
+    (url, rx)
+}
+
+fn response(status: &str, headers: &str, body: &[u8]) -> Vec<u8> {
+    let mut response = format!(
+        "HTTP/1.1 {}\r\nConnection: close\r\nContent-Length: {}\r\n{}\r\n",
+        status,
+        body.len(),
+        headers
+    )
+    .into_bytes();
+    response.extend_from_slice(body);
+    response
+}
+
+fn cache_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("hamcall-fetch-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn fetch_and_refresh() {
+    let newer = SAMPLE.replace("2023-11-25T20:31:27+00:00", "2023-12-01T00:00:00+00:00");
+    let (url, requests) = stub_server(vec![
+        response("200 OK", "ETag: \"v1\"\r\n", &gzip(SAMPLE)),
+        response("304 Not Modified", "", &[]),
+        response("200 OK", "ETag: \"v1\"\r\n", &gzip(SAMPLE)),
+        response("200 OK", "ETag: \"v2\"\r\n", &gzip(&newer)),
+    ]);
+    let dir = cache_dir("refresh");
+    let fetcher = Fetcher::new("secret", &dir).with_base_url(&url);
+
+    // Initial download
+    let data = fetcher.fetch().unwrap();
+    assert_eq!(data.date().to_rfc3339(), "2023-11-25T20:31:27+00:00");
+    assert!(fetcher.cache_path().is_file());
+    let request = requests.recv().unwrap();
+    assert!(request.starts_with("GET /cty.php?api=secret HTTP/1.1"));
+    assert!(!request.contains("If-None-Match"));
+
+    // Conditional request answered by the server
+    assert_eq!(fetcher.refresh().unwrap(), Refresh::NotModified);
+    assert!(requests.recv().unwrap().contains("If-None-Match: \"v1\""));
+
+    // Same data downloaded again
+    assert_eq!(fetcher.refresh().unwrap(), Refresh::NotModified);
+    requests.recv().unwrap();
+
+    // Newer data
+    assert_eq!(fetcher.refresh().unwrap(), Refresh::Downloaded);
+    requests.recv().unwrap();
+    let data = fetcher.fetch_cached().unwrap();
+    assert_eq!(data.date().to_rfc3339(), "2023-12-01T00:00:00+00:00");
+
+    // No request within the maximum age
+    let fetcher = fetcher.with_max_age(Duration::from_secs(3600));
+    assert_eq!(fetcher.refresh().unwrap(), Refresh::Cached);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn fetch_errors() {
+    let (url, _requests) = stub_server(vec![
+        response("403 Forbidden", "", &[]),
+        response("200 OK", "", b"<clublog>"),
+    ]);
+    let dir = cache_dir("errors");
+    let fetcher = Fetcher::new("invalid", &dir).with_base_url(&url);
+
+    assert!(matches!(fetcher.fetch(), Err(Error::Status(403))));
+    assert!(matches!(fetcher.fetch(), Err(Error::Parse(_))));
+    assert!(!fetcher.cache_path().exists());
+}
@@ -1,7 +1,11 @@
-use chrono::DateTime;
-use hamcall::clublog::ClubLog;
+use chrono::{DateTime, Utc};
+use hamcall::call::analyze_callsign;
+use hamcall::clublog::{
+    Adif, CallsignException, ClubLog, CqZone, Entity, InvalidOperation, Prefix, RecordId,
+    ZoneException,
+};
 use hamcall::clublogmap::ClubLogMap;
-use hamcall::clublogquery::ClubLogQuery;
+use hamcall::clublogquery::{ClubLogQuery, QueryIter};
 use lazy_static::lazy_static;
 use std::fs;
 
@@ -11,7 +15,7 @@ fn read_clublog_xml() -> &'static ClubLog {
             ClubLog::parse(&fs::read_to_string("data/clublog/cty.xml").unwrap()).unwrap();
     }
 
-    &*CLUBLOG
+    &CLUBLOG
 }
 
 #[test]
@@ -189,3 +193,280 @@ fn zone_exception_err_impl(clublog: &dyn ClubLogQuery) {
     );
     assert!(exception.is_none());
 }
+
+#[test]
+fn longest_prefix_match_ok() {
+    let clublog = read_clublog_xml();
+    longest_prefix_match_ok_impl(clublog);
+    let clublog = ClubLogMap::from(clublog.clone());
+    longest_prefix_match_ok_impl(&clublog);
+}
+
+fn longest_prefix_match_ok_impl(clublog: &dyn ClubLogQuery) {
+    let timestamp = DateTime::parse_from_rfc3339("2020-01-01T00:00:00Z")
+        .unwrap()
+        .into();
+
+    let info = clublog.longest_prefix_match("UA9ABC", &timestamp).unwrap();
+    assert_eq!(info.call, "UA9");
+    assert_eq!(info.adif, 15);
+
+    let info = clublog
+        .longest_prefix_match_with_appendix("SV1ABC", "A", &timestamp)
+        .unwrap();
+    assert_eq!(info.call, "SV/A");
+    assert_eq!(info.adif, 180);
+
+    assert!(clublog.longest_prefix_match("Q1ABC", &timestamp).is_none());
+}
+
+fn read_sample() -> ClubLog {
+    let mut clublog = ClubLog::from_path("tests/data/cty_sample.xml").unwrap();
+
+    // Additional prefixes of different length for the same callsigns
+    let template = clublog.prefixes.list[0].clone();
+    for (record, call, adif) in [(12, "D", 1), (13, "DL0", 1), (14, "DL/A", 1)] {
+        let mut prefix = template.clone();
+        prefix.record = record;
+        prefix.call = String::from(call);
+        prefix.adif = adif;
+        clublog.prefixes.list.push(prefix);
+    }
+
+    clublog
+}
+
+#[test]
+fn longest_prefix_match_sample() {
+    let clublog = read_sample();
+    longest_prefix_match_sample_impl(&clublog);
+    let clublog = ClubLogMap::from(clublog);
+    longest_prefix_match_sample_impl(&clublog);
+}
+
+fn longest_prefix_match_sample_impl(clublog: &dyn ClubLogQuery) {
+    let timestamp = DateTime::parse_from_rfc3339("2020-01-01T00:00:00Z")
+        .unwrap()
+        .into();
+
+    let records = [
+        ("DL1ABC", Some(10)),
+        ("DL0ABC", Some(13)),
+        ("DK1ABC", Some(12)),
+        ("VE3ABC", Some(11)),
+        ("XX1ABC", None),
+    ];
+    for (call, record) in records {
+        assert_eq!(
+            clublog
+                .longest_prefix_match(call, &timestamp)
+                .map(|p| p.record),
+            record,
+            "{}",
+            call
+        );
+    }
+
+    let info = clublog
+        .longest_prefix_match_with_appendix("DL1ABC", "A", &timestamp)
+        .unwrap();
+    assert_eq!(info.record, 14);
+    assert!(clublog
+        .longest_prefix_match_with_appendix("DL1ABC", "B", &timestamp)
+        .is_none());
+
+    let call = analyze_callsign(clublog, "DL1ABC/A", &timestamp).unwrap();
+    assert_eq!(call.adif, 1);
+    let call = analyze_callsign(clublog, "DL0XYZ", &timestamp).unwrap();
+    assert_eq!(call.adif, 1);
+    let call = analyze_callsign(clublog, "DL1XYZ", &timestamp).unwrap();
+    assert_eq!(call.adif, 230);
+}
+
+#[test]
+fn enumerate_entities_ok() {
+    let clublog = read_clublog_xml();
+    enumerate_entities_ok_impl(clublog);
+    let clublog = ClubLogMap::from(clublog.clone());
+    enumerate_entities_ok_impl(&clublog);
+}
+
+fn enumerate_entities_ok_impl(clublog: &dyn ClubLogQuery) {
+    let timestamp = DateTime::parse_from_rfc3339("2020-01-01T00:00:00Z")
+        .unwrap()
+        .into();
+
+    // Federal Republic of Germany is valid, German Democratic Republic is not
+    let adifs: Vec<Adif> = clublog.entities(&timestamp).map(|e| e.adif).collect();
+    assert!(adifs.contains(&230));
+    assert!(!adifs.contains(&229));
+
+    let mut adifs: Vec<Adif> = clublog.search_entities("germany").map(|e| e.adif).collect();
+    adifs.sort();
+    assert_eq!(adifs, vec![81, 229, 230]);
+
+    let entity = clublog.get_entity_by_prefix("SV/A", &timestamp).unwrap();
+    assert_eq!(entity.adif, 180);
+    assert!(clublog.prefixes_by_adif(180).any(|p| p.call == "SV/A"));
+}
+
+/// Implementation outside of the crate, relying on the default methods of the trait
+struct Minimal(ClubLog);
+
+impl ClubLogQuery for Minimal {
+    fn get_entity(&self, adif: Adif, timestamp: &DateTime<Utc>) -> Option<&Entity> {
+        self.0.get_entity(adif, timestamp)
+    }
+
+    fn get_prefix(&self, prefix: &str, timestamp: &DateTime<Utc>) -> Option<&Prefix> {
+        self.0.get_prefix(prefix, timestamp)
+    }
+
+    fn get_callsign_exception(
+        &self,
+        callsign: &str,
+        timestamp: &DateTime<Utc>,
+    ) -> Option<&CallsignException> {
+        self.0.get_callsign_exception(callsign, timestamp)
+    }
+
+    fn get_zone_exception(&self, callsign: &str, timestamp: &DateTime<Utc>) -> Option<CqZone> {
+        self.0.get_zone_exception(callsign, timestamp)
+    }
+
+    fn is_invalid_operation(&self, callsign: &str, timestamp: &DateTime<Utc>) -> bool {
+        self.0.is_invalid_operation(callsign, timestamp)
+    }
+
+    fn iter_entities(&self) -> QueryIter<'_, Entity> {
+        Box::new(self.0.entities.list.iter())
+    }
+
+    fn iter_prefixes(&self) -> QueryIter<'_, Prefix> {
+        Box::new(self.0.prefixes.list.iter())
+    }
+
+    fn iter_callsign_exceptions(&self) -> QueryIter<'_, CallsignException> {
+        Box::new(self.0.exceptions.list.iter())
+    }
+
+    fn iter_zone_exceptions(&self) -> QueryIter<'_, ZoneException> {
+        Box::new(self.0.zone_exceptions.list.iter())
+    }
+
+    fn iter_invalid_operations(&self) -> QueryIter<'_, InvalidOperation> {
+        Box::new(self.0.invalid_operations.list.iter())
+    }
+}
+
+#[test]
+fn enumerate_sample() {
+    let clublog = read_sample();
+    enumerate_sample_impl(&clublog);
+    let clublog = ClubLogMap::from(clublog);
+    enumerate_sample_impl(&clublog);
+    enumerate_sample_impl(&Minimal(read_sample()));
+}
+
+fn enumerate_sample_impl(clublog: &dyn ClubLogQuery) {
+    let timestamp = DateTime::parse_from_rfc3339("2020-01-01T00:00:00Z")
+        .unwrap()
+        .into();
+
+    let mut adifs: Vec<Adif> = clublog.entities(&timestamp).map(|e| e.adif).collect();
+    adifs.sort();
+    assert_eq!(adifs, vec![1, 230]);
+
+    let names: Vec<&str> = clublog
+        .search_entities("Republic OF")
+        .map(|e| e.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["FEDERAL REPUBLIC OF GERMANY"]);
+    assert_eq!(clublog.search_entities("xyz").count(), 0);
+
+    assert_eq!(
+        clublog.get_entity_by_prefix("VE", &timestamp).unwrap().adif,
+        1
+    );
+    assert!(clublog.get_entity_by_prefix("VE1", &timestamp).is_none());
+
+    let mut records: Vec<RecordId> = clublog.prefixes_by_adif(1).map(|p| p.record).collect();
+    records.sort();
+    assert_eq!(records, vec![11, 12, 13, 14]);
+
+    let records: Vec<RecordId> = clublog.exceptions_by_adif(1).map(|e| e.record).collect();
+    assert_eq!(records, vec![1]);
+    assert_eq!(clublog.exceptions_by_adif(230).count(), 0);
+}
+
+#[test]
+fn history_ok() {
+    let clublog = read_clublog_xml();
+    history_ok_impl(clublog);
+    let clublog = ClubLogMap::from(clublog.clone());
+    history_ok_impl(&clublog);
+}
+
+fn history_ok_impl(clublog: &dyn ClubLogQuery) {
+    // Y2 referenced the German Democratic Republic first and the Federal Republic of Germany afterwards
+    let adifs: Vec<Adif> = clublog
+        .prefix_history("Y2")
+        .iter()
+        .map(|p| p.adif)
+        .collect();
+    assert_eq!(adifs, vec![229, 230]);
+}
+
+#[test]
+fn history_sample() {
+    let mut clublog = read_sample();
+
+    let mut exception = clublog.exceptions.list[0].clone();
+    exception.record = 2;
+    exception.adif = 230;
+    exception.start = Some(
+        DateTime::parse_from_rfc3339("1990-01-01T00:00:00Z")
+            .unwrap()
+            .into(),
+    );
+    exception.end = Some(
+        DateTime::parse_from_rfc3339("1990-12-31T23:59:59Z")
+            .unwrap()
+            .into(),
+    );
+    clublog.exceptions.list.push(exception);
+
+    let mut operation = clublog.invalid_operations.list[0].clone();
+    operation.record = 21;
+    operation.start = None;
+    clublog.invalid_operations.list.push(operation);
+
+    history_sample_impl(&clublog);
+    history_sample_impl(&Minimal(clublog.clone()));
+    let clublog = ClubLogMap::from(clublog);
+    history_sample_impl(&clublog);
+}
+
+fn history_sample_impl(clublog: &dyn ClubLogQuery) {
+    let records: Vec<RecordId> = clublog
+        .callsign_exception_history("DL0ABC")
+        .iter()
+        .map(|e| e.record)
+        .collect();
+    assert_eq!(records, vec![2, 1]);
+
+    let records: Vec<RecordId> = clublog
+        .invalid_operation_history("DL0XX")
+        .iter()
+        .map(|o| o.record)
+        .collect();
+    assert_eq!(records, vec![21, 20]);
+
+    let history = clublog.zone_exception_history("VE1ABC");
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].zone, 2);
+    assert_eq!(clublog.prefix_history("DL").len(), 1);
+
+    assert!(clublog.callsign_exception_history("DL1ABC").is_empty());
+    assert!(clublog.prefix_history("XX").is_empty());
+}
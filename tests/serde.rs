@@ -0,0 +1,127 @@
+#![cfg(feature = "serde")]
+
+use chrono::{DateTime, Utc};
+use hamcall::call::{analyze_callsign, analyze_callsign_explained, Callsign, CallsignError};
+use hamcall::clublog::{ClubLog, InvalidOperation, Prefix};
+use hamcall::clublogmap::ClubLogMap;
+use hamcall::clublogquery::ClubLogQuery;
+use serde_json::json;
+
+fn read_sample() -> ClubLogMap {
+    ClubLogMap::from(ClubLog::from_path("tests/data/cty_sample.xml").unwrap())
+}
+
+fn timestamp(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value).unwrap().into()
+}
+
+#[test]
+fn callsign_json() {
+    let clublog = read_sample();
+
+    let call =
+        analyze_callsign(&clublog, "VE/DL1ABC/P", &timestamp("2020-01-01T00:00:00Z")).unwrap();
+    let value = serde_json::to_value(&call).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "call": "VE/DL1ABC/P",
+            "adif": 1,
+            "dxcc": "CANADA",
+            "cqzone": 5,
+            "continent": "NA",
+            "longitude": -80.0,
+            "latitude": 45.0,
+            "parts": {
+                "homecall": "DL1ABC",
+                "prefix": "VE",
+                "location_prefix": "VE",
+                "numeral": "1",
+                "suffix": "ABC",
+                "appendices": ["P"]
+            },
+            "whitelist": {"status": "not_applicable"}
+        })
+    );
+    assert_eq!(serde_json::from_value::<Callsign>(value).unwrap(), call);
+
+    let (_, explanation) =
+        analyze_callsign_explained(&clublog, "VE/DL1ABC/P", &timestamp("2020-01-01T00:00:00Z"));
+    let value = serde_json::to_value(&explanation).unwrap();
+    assert_eq!(
+        value["states"],
+        json!(["no_prefix", "single_prefix", "double_prefix", {"prefix_complete": 2}])
+    );
+    assert_eq!(value["parts"][0]["parttype"], json!("prefix"));
+}
+
+#[test]
+fn error_json() {
+    let clublog = read_sample();
+
+    let err = analyze_callsign(&clublog, "DL0XX", &timestamp("2001-06-01T00:00:00Z")).unwrap_err();
+    let value = serde_json::to_value(&err).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "kind": "invalid_operation",
+            "record": 20,
+            "start": "2001-01-01T00:00:00+00:00",
+            "end": "2001-12-31T23:59:59+00:00"
+        })
+    );
+    assert_eq!(serde_json::from_value::<CallsignError>(value).unwrap(), err);
+
+    let err =
+        analyze_callsign(&clublog, "DL1ABC//P", &timestamp("2020-01-01T00:00:00Z")).unwrap_err();
+    assert_eq!(
+        serde_json::to_string(&err).unwrap(),
+        r#"{"kind":"basic_format","position":7}"#
+    );
+}
+
+#[test]
+fn record_json() {
+    let clublog = read_sample();
+
+    let prefix = clublog
+        .get_prefix("DL", &timestamp("2020-01-01T00:00:00Z"))
+        .unwrap();
+    let value = serde_json::to_value(prefix).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "@record": 10,
+            "call": "DL",
+            "entity": "FEDERAL REPUBLIC OF GERMANY",
+            "adif": 230,
+            "cqz": 14,
+            "cont": "EU",
+            "long": 10.0,
+            "lat": 51.0
+        })
+    );
+    assert_eq!(serde_json::from_value::<Prefix>(value).unwrap(), *prefix);
+
+    let operation = clublog.invalid_operation_history("DL0XX")[0];
+    let value = serde_json::to_value(operation).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "@record": 20,
+            "call": "DL0XX",
+            "start": "2001-01-01T00:00:00+00:00",
+            "end": "2001-12-31T23:59:59+00:00"
+        })
+    );
+    assert_eq!(
+        serde_json::from_value::<InvalidOperation>(value.clone()).unwrap(),
+        *operation
+    );
+
+    // The error of the analysis carries the same timestamps in the same format
+    let err = analyze_callsign(&clublog, "DL0XX", &timestamp("2001-06-01T00:00:00Z")).unwrap_err();
+    let error = serde_json::to_value(&err).unwrap();
+    assert_eq!(error["start"], value["start"]);
+    assert_eq!(error["end"], value["end"]);
+}
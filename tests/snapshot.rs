@@ -0,0 +1,79 @@
+#![cfg(feature = "snapshot")]
+
+use chrono::DateTime;
+use hamcall::clublog::ClubLog;
+use hamcall::clublogmap::ClubLogMap;
+use hamcall::clublogquery::ClubLogQuery;
+use hamcall::clublogsnapshot::{self, Error};
+
+fn read_sample() -> ClubLog {
+    ClubLog::from_path("tests/data/cty_sample.xml").unwrap()
+}
+
+#[test]
+fn snapshot_roundtrip() {
+    let clublog = read_sample();
+    let date = clublog.date;
+    let clublogmap = ClubLogMap::from(clublog);
+
+    let mut snapshot = Vec::new();
+    clublogmap.write_snapshot(&mut snapshot).unwrap();
+
+    assert_eq!(clublogsnapshot::snapshot_date(&snapshot).unwrap(), date);
+
+    let loaded = ClubLogMap::from_snapshot(&snapshot).unwrap();
+    assert_eq!(loaded, clublogmap);
+    assert_eq!(loaded.date(), date);
+
+    let timestamp = DateTime::parse_from_rfc3339("2000-06-01T00:00:00Z")
+        .unwrap()
+        .into();
+    assert_eq!(loaded.get_prefix("VE", &timestamp).unwrap().adif, 1);
+    assert_eq!(
+        loaded
+            .get_callsign_exception("DL0ABC", &timestamp)
+            .unwrap()
+            .record,
+        1
+    );
+}
+
+#[test]
+fn snapshot_invalid() {
+    let clublogmap = ClubLogMap::from(read_sample());
+    let mut snapshot = Vec::new();
+    clublogmap.write_snapshot(&mut snapshot).unwrap();
+
+    let res = ClubLogMap::from_snapshot(&snapshot[..snapshot.len() - 1]);
+    assert!(matches!(res, Err(Error::Corrupted)));
+
+    let res = ClubLogMap::from_snapshot(b"<?xml version=\"1.0\"?>");
+    assert!(matches!(res, Err(Error::InvalidMagic)));
+
+    snapshot[8] = 0xff;
+    let res = ClubLogMap::from_snapshot(&snapshot);
+    assert!(matches!(res, Err(Error::UnsupportedVersion(_))));
+}
+
+#[test]
+fn snapshot_reproducible() {
+    let clublogmap = ClubLogMap::from(read_sample());
+    let mut snapshot = Vec::new();
+    clublogmap.write_snapshot(&mut snapshot).unwrap();
+
+    // Writing the loaded data must result in exactly the same bytes
+    let loaded = ClubLogMap::from_snapshot(&snapshot).unwrap();
+    let mut rewritten = Vec::new();
+    loaded.write_snapshot(&mut rewritten).unwrap();
+    assert_eq!(rewritten, snapshot);
+
+    let mut clublog = read_sample();
+    clublog.entities.list.reverse();
+    clublog.exceptions.list.reverse();
+    clublog.prefixes.list.reverse();
+    let mut reordered = Vec::new();
+    ClubLogMap::from(clublog)
+        .write_snapshot(&mut reordered)
+        .unwrap();
+    assert_eq!(reordered, snapshot);
+}